@@ -1,12 +1,17 @@
 use std::{
+    collections::HashMap,
     error::Error,
     fs::{read_to_string, write},
     path::{Path, PathBuf},
 };
 
-use clap::{Parser, Subcommand};
-use console::Term;
-use humansort_lib::HumansortState;
+use clap::{Parser, Subcommand, ValueEnum};
+use console::{Key, Term};
+use humansort_lib::{
+    ActiveLearning, Bracket, BradleyTerry, Elo, ExactSort, ExactStep, Glicko2, HumansortState,
+    Insertion, ItemId, Judgment, MergeOptions, Model, NewRating, Outcome, Quadratic, RoundRobin,
+    Session, Strategy, Swiss, TopK,
+};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -26,6 +31,9 @@ enum Commands {
         /// <INFILE>.humansort)
         #[arg(value_name = "OUTFILE")]
         hs_file: Option<PathBuf>,
+        /// Rating model used to turn judgments into ratings
+        #[arg(long, value_enum, default_value_t = ModelArg::Elo)]
+        model: ModelArg,
     },
     /// Update a humansort file with new inputs; adds new items and removes
     /// missing ones
@@ -36,24 +44,219 @@ enum Commands {
         /// Name of the humansort file to be updated
         #[arg(value_name = "OUTFILE")]
         hs_file: PathBuf,
+        /// Keep missing items in the file instead of deleting them, so that
+        /// they come back with their ratings if they're added again later
+        #[arg(long)]
+        archive: bool,
+        /// Where new items start
+        #[arg(long, value_enum, default_value_t = NewRatingArg::Unrated)]
+        new_rating: NewRatingArg,
+        /// Start new items at this rating
+        #[arg(long, value_name = "RATING", conflicts_with = "new_rating")]
+        prior: Option<f32>,
+        /// File of renames to apply first, one per line, with the old text and
+        /// the new text separated by a tab
+        #[arg(long, value_name = "FILE")]
+        rename_map: Option<PathBuf>,
+        /// Treat missing items as renamed to new lines that are at least this
+        /// similar to them (from 0 to 1) without asking; less similar pairs are
+        /// still offered for confirmation
+        #[arg(long, value_name = "SIMILARITY")]
+        auto_rename: Option<f32>,
+        /// Place the new items into the existing ranking with a few questions
+        /// each, the next time the file is sorted
+        #[arg(long, conflicts_with_all = ["new_rating", "prior"])]
+        insert: bool,
     },
     /// Read a humansort file and interactively sort it
     Sort {
         /// Humansort file to be sorted
         #[arg(value_name = "INFILE")]
         hs_file: PathBuf,
-        /// Number of items to prompt the user to sort in a single iteration
+        /// Number of items to prompt the user to sort in a single iteration;
+        /// saved in the file (defaults to 5 for new files)
         #[arg(value_name = "NUM_ITEMS")]
         maybe_num_items: Option<usize>,
+        /// Put the items in order by typing their numbers from best to worst,
+        /// instead of only picking the best one; press enter to submit a
+        /// partial ranking
+        #[arg(long, conflicts_with = "worst")]
+        rank: bool,
+        /// Pick the worst item in each set instead of the best one
+        #[arg(long)]
+        worst: bool,
+        /// How to choose which items to show; saved in the file (defaults to
+        /// quadratic for new files)
+        #[arg(long, value_enum)]
+        strategy: Option<StrategyArg>,
+        /// Stop automatically once the ranking is roughly this many percent
+        /// settled
+        #[arg(long, value_name = "PERCENT")]
+        target: Option<f32>,
+        /// Only work out which N items belong at the top, without caring
+        /// about the order of the rest; stops once that set is settled; saved
+        /// in the file
+        #[arg(long, value_name = "N", conflicts_with = "strategy")]
+        top_k: Option<usize>,
+        /// Choose items deterministically from this seed, so that the same
+        /// answers always lead to the same prompts, ratings and file (answers
+        /// are numbered instead of timed); the seed is saved in the file
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Show every item at least this many times, so that new and
+        /// low-rated items aren't left out; saved in the file (defaults to 3
+        /// for new files)
+        #[arg(long, value_name = "N")]
+        min_appearances: Option<u32>,
+        /// Question to show above the items, such as "Which would you rather
+        /// read?"; saved in the file
+        #[arg(long)]
+        question: Option<String>,
+        /// Put the items in an exact order by comparing two at a time, with
+        /// as few questions as possible; progress is saved in the file, and
+        /// a plain sort picks it back up
+        #[arg(long, conflicts_with_all = ["strategy", "top_k", "target"])]
+        exact: bool,
+        /// Play a tournament between the top items, and show the standings
+        /// when it's over; the results count toward the ratings too
+        #[arg(
+            long,
+            value_enum,
+            conflicts_with_all = ["strategy", "top_k", "target", "exact"]
+        )]
+        tournament: Option<TournamentArg>,
+        /// How many of the top items to enter in the tournament (defaults to
+        /// all of them)
+        #[arg(long, value_name = "N", requires = "tournament")]
+        entrants: Option<usize>,
+        /// Number of rounds in a Swiss tournament (defaults to enough to
+        /// find a clear winner)
+        #[arg(long, value_name = "N", requires = "tournament")]
+        rounds: Option<usize>,
+    },
+    /// Takes back the most recent judgment in a humansort file
+    Undo {
+        /// Humansort file to be updated
+        #[arg(value_name = "INFILE")]
+        hs_file: PathBuf,
+    },
+    /// Makes the most recently undone judgment in a humansort file again
+    Redo {
+        /// Humansort file to be updated
+        #[arg(value_name = "INFILE")]
+        hs_file: PathBuf,
+    },
+    /// Rebuilds all ratings in a humansort file by replaying its history,
+    /// optionally under a different rating model
+    Recompute {
+        /// Humansort file to be updated
+        #[arg(value_name = "INFILE")]
+        hs_file: PathBuf,
+        /// Rating model to switch to (defaults to the file's current model)
+        #[arg(long, value_enum)]
+        model: Option<ModelArg>,
+        /// K-factor for the Elo model
+        #[arg(long)]
+        k_factor: Option<f32>,
+        /// Rating difference that makes one item ten times as likely to win as
+        /// another under the Elo model
+        #[arg(long)]
+        scale: Option<f32>,
+        /// System constant for the Glicko-2 model
+        #[arg(long)]
+        tau: Option<f32>,
     },
     /// Reads a humansort file and outputs a sorted list
     Output {
         /// Humansort file to be printed
         #[arg(value_name = "INFILE")]
         hs_file: PathBuf,
+        /// Print each item's rating, plus its uncertainty if the rating model
+        /// tracks one
+        #[arg(long)]
+        ratings: bool,
+        /// How to order the items
+        #[arg(long, value_enum, default_value_t = RankingArg::Model)]
+        ranking: RankingArg,
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum StrategyArg {
+    /// Random items, favoring the top of the list
+    Quadratic,
+    /// The items whose comparison would tell the most about the ranking
+    ActiveLearning,
+}
+
+impl From<StrategyArg> for Strategy {
+    fn from(arg: StrategyArg) -> Self {
+        match arg {
+            StrategyArg::Quadratic => Strategy::Quadratic(Quadratic),
+            StrategyArg::ActiveLearning => Strategy::ActiveLearning(ActiveLearning),
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum NewRatingArg {
+    /// The same rating as every item in a new file
+    Unrated,
+    /// The mean rating of the items already in the file
+    Mean,
+    /// The median rating of the items already in the file
+    Median,
+}
+
+impl From<NewRatingArg> for NewRating {
+    fn from(arg: NewRatingArg) -> Self {
+        match arg {
+            NewRatingArg::Unrated => NewRating::Unrated,
+            NewRatingArg::Mean => NewRating::Mean,
+            NewRatingArg::Median => NewRating::Median,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum RankingArg {
+    /// Order by the ratings kept up to date by the file's rating model
+    Model,
+    /// Fit a Bradley–Terry model to every judgment at once
+    BradleyTerry,
+    /// The order found by a finished `sort --exact`
+    Exact,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum TournamentArg {
+    /// Every entrant plays every other once
+    RoundRobin,
+    /// Entrants with the same score play each other, for a few rounds
+    Swiss,
+    /// A bracket where one loss knocks an entrant out
+    SingleElimination,
+    /// A bracket where it takes two losses to knock an entrant out
+    DoubleElimination,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ModelArg {
+    /// Elo-style updates with no notion of uncertainty
+    Elo,
+    /// Glicko-2, which tracks how settled each rating is
+    Glicko2,
+}
+
+impl From<ModelArg> for Model {
+    fn from(arg: ModelArg) -> Self {
+        match arg {
+            ModelArg::Elo => Model::Elo(Elo::default()),
+            ModelArg::Glicko2 => Model::Glicko2(Glicko2::default()),
+        }
+    }
+}
+
 fn read_input_file(input_file: &Path) -> Result<Vec<String>, Box<dyn Error>> {
     Ok(read_to_string(input_file)?
         .lines()
@@ -61,6 +264,145 @@ fn read_input_file(input_file: &Path) -> Result<Vec<String>, Box<dyn Error>> {
         .collect())
 }
 
+/// Read a file of renames, where each line is the old text and the new text
+/// separated by a tab.
+fn read_rename_map(rename_map: &Path) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut renames = Vec::new();
+    for (idx, line) in read_to_string(rename_map)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.split_once('\t') {
+            Some((old, new)) => renames.push((old.to_string(), new.to_string())),
+            None => {
+                return Err(format!(
+                    "Line {} of the rename map should be the old text and the new text \
+                     separated by a tab",
+                    idx + 1
+                )
+                .into())
+            }
+        }
+    }
+    Ok(renames)
+}
+
+/// Ask a yes-or-no question.
+fn confirm(term: &Term, question: &str) -> Result<bool, Box<dyn Error>> {
+    term.write_str(&format!("{} [y/n] ", question))?;
+    let answer = loop {
+        match term.read_key()? {
+            Key::Char('y') => break true,
+            Key::Char('n') => break false,
+            Key::Unknown => return Err("Not a terminal".into()),
+            _ => {}
+        }
+    };
+    term.write_line("")?;
+    Ok(answer)
+}
+
+/// What the user asked for while being shown a set of items.
+enum Choice {
+    Quit,
+    Undo,
+    Redo,
+    Tie,
+    Abstain,
+    Pick(usize),
+    Ranking(Vec<usize>),
+}
+
+/// Print `items` and wait for the user to make a choice about them. In
+/// ranking mode, digits are collected until every item (or every item but the
+/// last) is in order, or until the user presses enter.
+fn read_choice(term: &Term, items: &[String], rank: bool) -> Result<Choice, Box<dyn Error>> {
+    let mut order: Vec<usize> = Vec::new();
+    loop {
+        // Print the options, marking the ones that have been ranked so far.
+        for (idx, item) in items.iter().enumerate() {
+            match order.iter().position(|&o| o == idx) {
+                Some(pos) => term.write_line(&format!("({}) {}  ← #{}", idx + 1, item, pos + 1))?,
+                None => term.write_line(&format!("({}) {}", idx + 1, item))?,
+            }
+        }
+
+        let idx = match term.read_key()? {
+            Key::Char('q') => return Ok(Choice::Quit),
+            Key::Char('u') => return Ok(Choice::Undo),
+            Key::Char('r') => return Ok(Choice::Redo),
+            Key::Char('t') => return Ok(Choice::Tie),
+            Key::Char('s') => return Ok(Choice::Abstain),
+            Key::Char(c) => c
+                .to_digit(10)
+                .filter(|d| (1..=items.len() as u32).contains(d))
+                .map(|d| d as usize - 1),
+            Key::Enter if rank && !order.is_empty() => return Ok(Choice::Ranking(order)),
+            Key::Backspace => {
+                order.pop();
+                None
+            }
+            Key::Unknown => return Err("Not a terminal".into()),
+            _ => None,
+        };
+        match idx {
+            Some(idx) if !rank => return Ok(Choice::Pick(idx)),
+            Some(idx) if !order.contains(&idx) => {
+                order.push(idx);
+                // The last item's place is implied by the others.
+                if order.len() + 1 == items.len() {
+                    return Ok(Choice::Ranking(order));
+                }
+            }
+            _ => {}
+        }
+        term.clear_last_lines(items.len())?;
+    }
+}
+
+/// An item's text, in quotes, or a placeholder if it has been removed.
+fn quoted(humansort: &HumansortState, id: ItemId) -> String {
+    match humansort.item(id) {
+        Some(item) => format!("'{}'", item),
+        None => "a removed item".to_string(),
+    }
+}
+
+fn quoted_list<'a>(humansort: &HumansortState, ids: impl Iterator<Item = &'a ItemId>) -> String {
+    ids.map(|&id| quoted(humansort, id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe_judgment(humansort: &HumansortState, judgment: &Judgment) -> String {
+    match &judgment.outcome {
+        Outcome::Winner { item } => {
+            let others = quoted_list(humansort, judgment.shown.iter().filter(|s| *s != item));
+            format!("{} over {}", quoted(humansort, *item), others)
+        }
+        Outcome::Loser { item } => {
+            let others = quoted_list(humansort, judgment.shown.iter().filter(|s| *s != item));
+            format!("{} over {}", others, quoted(humansort, *item))
+        }
+        Outcome::Tie => format!(
+            "a tie between {}",
+            quoted_list(humansort, judgment.shown.iter())
+        ),
+        Outcome::Abstain => format!("skipping {}", quoted_list(humansort, judgment.shown.iter())),
+        Outcome::Ranking { order } => {
+            let mut ranked: Vec<String> = order.iter().map(|&id| quoted(humansort, id)).collect();
+            let unranked = quoted_list(
+                humansort,
+                judgment.shown.iter().filter(|s| !order.contains(s)),
+            );
+            if !unranked.is_empty() {
+                ranked.push(unranked);
+            }
+            ranked.join(" > ")
+        }
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
 
@@ -68,11 +410,13 @@ fn main() -> Result<(), Box<dyn Error>> {
         Commands::New {
             input_file,
             hs_file,
+            model,
         } => {
             let infile = read_input_file(&input_file)?;
 
             // Convert input file to humansort state.
-            let humansort: HumansortState = infile.into();
+            let mut humansort: HumansortState = infile.into();
+            humansort.set_model(model.into());
 
             // Write the humansort state to the output file.
             let output = serde_json::to_string_pretty(&humansort)?;
@@ -91,7 +435,16 @@ fn main() -> Result<(), Box<dyn Error>> {
         Commands::Merge {
             input_file,
             hs_file,
+            archive,
+            new_rating,
+            prior,
+            rename_map,
+            auto_rename,
+            insert,
         } => {
+            // Below this, pairs are too different to be worth asking about.
+            const MIN_RENAME_SIMILARITY: f32 = 0.6;
+
             // Read input file.
             let new_items = read_input_file(&input_file)?;
 
@@ -99,9 +452,76 @@ fn main() -> Result<(), Box<dyn Error>> {
             let outfile = read_to_string(hs_file.clone())?;
             let mut humansort = serde_json::from_str::<HumansortState>(&outfile)?;
 
-            // Update the humansort state by deleting missing items and adding
-            // new ones.
-            humansort.merge(&new_items);
+            // Carry ratings over to renamed lines, starting with the renames
+            // the user listed.
+            if let Some(rename_map) = rename_map {
+                for (old, new) in read_rename_map(&rename_map)? {
+                    match humansort.id_of(&old) {
+                        Ok(id) => {
+                            humansort.rename_item(id, &new)?;
+                            println!("Renamed '{}' to '{}'", old, new);
+                        }
+                        // An earlier merge with the same map already did it.
+                        Err(_) if humansort.id_of(&new).is_ok() => {}
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+            }
+            // Then look for lines that look like edits of missing items,
+            // including any less similar ones that the user is happy to
+            // rename without asking.
+            let term = Term::stdout();
+            let threshold =
+                auto_rename.map_or(MIN_RENAME_SIMILARITY, |t| t.min(MIN_RENAME_SIMILARITY));
+            let mut unconfirmed = 0;
+            for candidate in humansort.rename_candidates(&new_items, threshold) {
+                let question = format!(
+                    "Was '{}' renamed to '{}'?",
+                    candidate.old_value, candidate.new_value
+                );
+                let renamed = if auto_rename.is_some_and(|t| candidate.similarity >= t) {
+                    true
+                } else if term.is_term() {
+                    confirm(&term, &question)?
+                } else {
+                    unconfirmed += 1;
+                    false
+                };
+                if renamed {
+                    humansort.rename_item(candidate.id, &candidate.new_value)?;
+                    println!(
+                        "Renamed '{}' to '{}'",
+                        candidate.old_value, candidate.new_value
+                    );
+                }
+            }
+            if unconfirmed > 0 {
+                println!(
+                    "Not renaming {} items that look like they were edited without \
+                     confirmation; run merge in a terminal, or pass --auto-rename",
+                    unconfirmed
+                );
+            }
+
+            // Update the humansort state by deleting (or archiving) missing
+            // items and adding new ones.
+            let options = MergeOptions {
+                archive_missing: archive,
+                new_rating: match prior {
+                    Some(rating) => NewRating::Prior(rating),
+                    None => new_rating.into(),
+                },
+            };
+            let added = humansort.merge_with(&new_items, &options)?;
+            if insert && !added.is_empty() {
+                let insertion = Insertion::new(&humansort, &added);
+                println!(
+                    "Run sort to place {} new items with at most {} questions",
+                    added.len(),
+                    insertion.questions_left(&humansort)
+                );
+                humansort.start_session(Session::Insertion(insertion));
+            }
 
             // Write updated state to the original file.
             let output = serde_json::to_string_pretty(&humansort)?;
@@ -110,87 +530,360 @@ fn main() -> Result<(), Box<dyn Error>> {
         Commands::Sort {
             hs_file,
             maybe_num_items,
+            rank,
+            worst,
+            strategy,
+            target,
+            top_k,
+            seed,
+            min_appearances,
+            question,
+            exact,
+            tournament,
+            entrants,
+            rounds,
         } => {
             // Read and parse humansort file.
             let infile = read_to_string(hs_file.clone())?;
             let mut humansort = serde_json::from_str::<HumansortState>(&infile)?;
-
-            let num_items = if let Some(n) = maybe_num_items {
-                if (2..=9).contains(&n) {
-                    humansort.set_num_items(n)?;
-                    n
-                } else {
-                    return Err(format!(
-                        "Number of items must be between 2 and 9, inclusive (found {})",
-                        n
-                    )
-                    .into());
+            if seed.is_some() {
+                humansort.set_seed(seed);
+            }
+            if let Some(n) = min_appearances {
+                humansort.set_min_appearances(n);
+            }
+            if let Some(k) = top_k {
+                humansort.set_selection(Strategy::TopK(TopK { k }));
+            } else if let Some(strategy) = strategy {
+                humansort.set_selection(strategy.into());
+            }
+            // Choosing how to pick items ends any session in the file, and
+            // otherwise an unfinished one carries on.
+            if exact {
+                humansort.start_session(Session::Exact(ExactSort));
+            } else if let Some(tournament) = tournament {
+                let ids: Vec<ItemId> = humansort
+                    .ranked()
+                    .map(|item| item.id)
+                    .take(entrants.unwrap_or(usize::MAX))
+                    .collect();
+                if ids.len() < 2 {
+                    return Err("A tournament needs at least 2 entrants".into());
                 }
-            } else {
-                humansort.num_items()
+                let session = match tournament {
+                    TournamentArg::RoundRobin => {
+                        Session::RoundRobin(RoundRobin::new(&humansort, &ids))
+                    }
+                    TournamentArg::Swiss => {
+                        let mut swiss = Swiss::new(&humansort, &ids);
+                        if let Some(rounds) = rounds {
+                            swiss.rounds = rounds;
+                        }
+                        Session::Swiss(swiss)
+                    }
+                    TournamentArg::SingleElimination | TournamentArg::DoubleElimination => {
+                        let double = tournament == TournamentArg::DoubleElimination;
+                        Session::Bracket(Bracket::new(&humansort, &ids, double))
+                    }
+                };
+                humansort.start_session(session);
+            } else if top_k.is_some() || strategy.is_some() {
+                humansort.end_session();
+            }
+            if question.is_some() {
+                humansort.set_question(question);
+            }
+            // A top-k strategy saved in the file also decides when to stop.
+            let top_k = match humansort.selection() {
+                Strategy::TopK(TopK { k }) => Some(*k),
+                _ => None,
             };
 
+            // Items are picked with the number keys, so there can't be more
+            // than nine of them.
+            let num_items = maybe_num_items.unwrap_or_else(|| humansort.num_items());
+            if !(2..=9).contains(&num_items) {
+                return Err(format!(
+                    "Number of items must be between 2 and 9, inclusive (found {})",
+                    num_items
+                )
+                .into());
+            }
+            humansort.set_num_items(num_items)?;
+            // Save the settings now, in case the user quits right away.
+            write(hs_file.clone(), serde_json::to_string_pretty(&humansort)?)?;
+
+            // Leave room for the items plus a line saying how settled the
+            // ranking is, and the question if there is one. Sessions compare
+            // two items at a time.
+            let num_shown = match humansort.session() {
+                Some(_) => 2,
+                None => num_items,
+            };
+            let num_lines = num_shown + 1 + humansort.question().map_or(0, |_| 1);
             let term = Term::stdout();
-            for _ in 0..num_items {
+            for _ in 0..num_lines {
                 term.write_line("")?;
             }
 
             loop {
                 // Clear lines.
-                term.clear_last_lines(num_items)?;
+                term.clear_last_lines(num_lines)?;
 
-                // Check for stopping criterion and notify the user as
-                // appropriate.
-                // TODO
-
-                // Get options and print them.
-                let items = humansort.next()?;
-                for (idx, item) in items.iter().enumerate() {
-                    term.write_line(&format!("({}) {}", idx + 1, *item))?;
-                }
-
-                // Get user's choice.
-                let mut choice = ' ';
-                while !choice.is_ascii_digit() && choice != 'q' {
-                    choice = term.read_char()?;
-                }
-                // Quit if the user said so.
-                if choice == 'q' {
-                    term.clear_last_lines(num_items)?;
-                    break;
+                // A session picks its own items, and says when it's done.
+                let items = if let Some(session) = humansort.session() {
+                    let Some(items) = humansort.session_prompt() else {
+                        // Show what the session found.
+                        match session {
+                            Session::Exact(exact) => {
+                                if let ExactStep::Done(order) = exact.step(&humansort) {
+                                    for item in order.iter().filter_map(|&id| humansort.item(id)) {
+                                        term.write_line(&item.to_string())?;
+                                    }
+                                }
+                            }
+                            Session::Insertion(_) => {
+                                term.write_line("The new items are in place")?;
+                            }
+                            _ => {
+                                let standings = session.tournament(&humansort).unwrap().standings;
+                                for (place, standing) in standings.iter().enumerate() {
+                                    if let Some(item) = humansort.item(standing.id) {
+                                        term.write_line(&format!(
+                                            "{}. {} ({} points)",
+                                            place + 1,
+                                            item,
+                                            standing.points
+                                        ))?;
+                                    }
+                                }
+                            }
+                        }
+                        humansort.end_session();
+                        write(hs_file.clone(), serde_json::to_string_pretty(&humansort)?)?;
+                        break;
+                    };
+                    term.write_line(&format!(
+                        "At most {} more questions",
+                        session.questions_left(&humansort)
+                    ))?;
+                    items
+                } else {
+                    // Get options and check for the stopping criterion.
+                    let prompt = humansort.prompt()?;
+                    let settled = (prompt.convergence * 100.).round();
+                    if let Some(target) = target {
+                        if settled >= target {
+                            term.write_line(&format!(
+                                "Roughly {}% settled, which meets the target of {}%",
+                                settled, target
+                            ))?;
+                            break;
+                        }
+                    }
+                    if let Some(k) = top_k {
+                        if humansort.top_k_settled(k) {
+                            term.write_line(&format!("The top {} items are settled", k))?;
+                            break;
+                        }
+                        term.write_line(&format!(
+                            "Roughly {}% settled; the top {} items aren't settled yet",
+                            settled, k
+                        ))?;
+                    } else {
+                        term.write_line(&format!("Roughly {}% settled", settled))?;
+                    }
+                    prompt.items
+                };
+                if let Some(question) = humansort.question() {
+                    term.write_line(question)?;
                 }
-                // Otherwise, try to convert their choice into a number.
-                let choice_idx = (choice.to_digit(10).unwrap() - 1) as usize;
 
-                // Update sort state.
-                let mut new_data = vec![items[choice_idx].clone()];
-                for (idx, item) in items.iter().enumerate() {
-                    if idx == choice_idx {
-                        continue;
+                // Get the user's choice about the options.
+                let values: Vec<String> = items
+                    .iter()
+                    .filter_map(|&id| humansort.item(id))
+                    .map(|item| item.to_string())
+                    .collect();
+                match read_choice(&term, &values, rank)? {
+                    // Quit if the user said so.
+                    Choice::Quit => {
+                        term.clear_last_lines(num_lines)?;
+                        break;
+                    }
+                    // Take back the last judgment or make it again. If there's
+                    // nothing to undo or redo, just move on to new items.
+                    Choice::Undo => {
+                        let _ = humansort.undo();
+                    }
+                    Choice::Redo => {
+                        let _ = humansort.redo();
+                    }
+                    // Otherwise, update sort state.
+                    Choice::Tie => {
+                        humansort.tie(&items)?;
+                    }
+                    Choice::Abstain => {
+                        humansort.abstain(&items)?;
+                    }
+                    Choice::Pick(idx) if worst => {
+                        humansort.judge_loser(&items, items[idx])?;
+                    }
+                    Choice::Pick(idx) => {
+                        humansort.judge(&items, items[idx])?;
+                    }
+                    Choice::Ranking(order) => {
+                        let order: Vec<ItemId> = order.iter().map(|&i| items[i]).collect();
+                        humansort.rank(&items, &order)?;
                     }
-                    new_data.push(item.clone());
                 }
-                humansort.update(&new_data)?;
 
                 // Write the new state to the input file.
                 let output = serde_json::to_string_pretty(&humansort)?;
                 write(hs_file.clone(), output)?;
             }
         }
-        Commands::Output { hs_file } => {
+        Commands::Undo { hs_file } => {
+            // Read and parse humansort file.
+            let infile = read_to_string(hs_file.clone())?;
+            let mut humansort = serde_json::from_str::<HumansortState>(&infile)?;
+
+            let judgment = humansort.undo()?;
+            println!("Undid {}", describe_judgment(&humansort, &judgment));
+
+            // Write updated state to the original file.
+            let output = serde_json::to_string_pretty(&humansort)?;
+            write(hs_file, output)?;
+        }
+        Commands::Redo { hs_file } => {
+            // Read and parse humansort file.
+            let infile = read_to_string(hs_file.clone())?;
+            let mut humansort = serde_json::from_str::<HumansortState>(&infile)?;
+
+            let judgment = humansort.redo()?;
+            println!("Redid {}", describe_judgment(&humansort, &judgment));
+
+            // Write updated state to the original file.
+            let output = serde_json::to_string_pretty(&humansort)?;
+            write(hs_file, output)?;
+        }
+        Commands::Recompute {
+            hs_file,
+            model,
+            k_factor,
+            scale,
+            tau,
+        } => {
+            // Read and parse humansort file.
+            let infile = read_to_string(hs_file.clone())?;
+            let mut humansort = serde_json::from_str::<HumansortState>(&infile)?;
+
+            // Start from the requested model, or the current one, and apply
+            // any parameter overrides.
+            let mut new_model = match model {
+                Some(m) => m.into(),
+                None => humansort.model().clone(),
+            };
+            match &mut new_model {
+                Model::Elo(elo) => {
+                    if tau.is_some() {
+                        return Err("--tau only applies to the glicko2 model".into());
+                    }
+                    elo.k_factor = k_factor.unwrap_or(elo.k_factor);
+                    elo.scale = scale.unwrap_or(elo.scale);
+                }
+                Model::Glicko2(glicko2) => {
+                    if k_factor.is_some() || scale.is_some() {
+                        return Err("--k-factor and --scale only apply to the elo model".into());
+                    }
+                    glicko2.tau = tau.unwrap_or(glicko2.tau);
+                }
+            }
+
+            let old_ranks: HashMap<ItemId, usize> =
+                humansort.ranked().map(|i| (i.id, i.rank)).collect();
+            humansort.replay(new_model)?;
+
+            // Report how far each item moved, in its new order.
+            let mut any_moved = false;
+            for item in humansort.ranked() {
+                let old_rank = old_ranks[&item.id];
+                let moved = old_rank as i64 - item.rank as i64;
+                if moved != 0 {
+                    any_moved = true;
+                    println!(
+                        "{:+4}  {} ({} → {})",
+                        moved, item.value, old_rank, item.rank
+                    );
+                }
+            }
+            if !any_moved {
+                println!("No items changed rank");
+            }
+
+            // Write updated state to the original file.
+            let output = serde_json::to_string_pretty(&humansort)?;
+            write(hs_file, output)?;
+        }
+        Commands::Output {
+            hs_file,
+            ratings,
+            ranking,
+        } => {
             // Read and parse humansort file.
             let infile = read_to_string(hs_file)?;
             let humansort = serde_json::from_str::<HumansortState>(&infile)?;
 
-            // Print all items in descending order by rating.
+            // Format all items in descending order by rating.
+            let lines: Vec<String> = match ranking {
+                RankingArg::Model => humansort
+                    .ranked()
+                    .map(|item| match item.uncertainty {
+                        _ if !ratings => item.value.to_string(),
+                        Some(u) => format!("{}\t{:.1} ± {:.1}", item.value, item.rating, u),
+                        None => format!("{}\t{:.3}", item.value, item.rating),
+                    })
+                    .collect(),
+                RankingArg::BradleyTerry => BradleyTerry::default()
+                    .fit(&humansort)?
+                    .into_iter()
+                    .filter_map(|(id, score)| {
+                        let item = humansort.item(id)?;
+                        Some(if ratings {
+                            format!("{}\t{:.3}", item, score)
+                        } else {
+                            item.to_string()
+                        })
+                    })
+                    .collect(),
+                RankingArg::Exact => match ExactSort.step(&humansort) {
+                    ExactStep::Done(order) => order
+                        .into_iter()
+                        .filter_map(|id| humansort.item(id))
+                        .map(|item| match ratings {
+                            true => format!("{}\t{:.3}", item, item.rating()),
+                            false => item.to_string(),
+                        })
+                        .collect(),
+                    ExactStep::Compare(_) => {
+                        return Err(format!(
+                            "The exact order isn't settled yet; finish it with sort --exact \
+                             (at most {} more questions)",
+                            ExactSort.questions_left(&humansort)
+                        )
+                        .into())
+                    }
+                },
+            };
+
             let term = Term::stdout();
-            for item in humansort {
+            for line in lines {
                 // If the output of humansort is a pipe instead of stdout, then
                 // the pipe can break before we finish writing our items. (For
                 // example, this happens when piping to head.) Rather than
                 // throwing an error in that case, we just stop sending items
                 // early.
-                if term.write_line(&format!("{}", item)).is_err() {
+                if term.write_line(&line).is_err() {
                     break;
                 }
             }
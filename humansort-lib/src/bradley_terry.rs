@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use crate::{HumansortError, HumansortState, ItemId};
+
+/// A batch maximum-likelihood fit of the Bradley–Terry model to every
+/// comparison in a state's history. Unlike the online rating models, the
+/// result doesn't depend on the order in which judgments were made.
+///
+/// Every item also plays `prior` virtual wins and `prior` virtual losses
+/// against an imaginary item with a score of zero. This keeps scores finite
+/// for items that have never lost (or never won) and puts items from
+/// separate groups that were never compared against each other on a common
+/// scale, rather than letting their scores drift apart arbitrarily, so it has
+/// to be positive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BradleyTerry {
+    pub prior: f64,
+    pub max_iterations: usize,
+    pub tolerance: f64,
+}
+
+impl Default for BradleyTerry {
+    fn default() -> Self {
+        BradleyTerry {
+            prior: 1.,
+            max_iterations: 10_000,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+impl BradleyTerry {
+    /// Fit scores to `state`'s history and return every current item with its
+    /// score (the natural log of its strength), sorted descending by score.
+    /// Judgments involving items that no longer exist only count the items
+    /// that remain.
+    pub fn fit(&self, state: &HumansortState) -> Result<Vec<(ItemId, f64)>, HumansortError> {
+        if !(self.prior.is_finite() && self.prior > 0.) {
+            return Err(HumansortError::InvalidModel(format!(
+                "prior must be a positive number (got {})",
+                self.prior
+            )));
+        }
+        let ids: Vec<ItemId> = state.items.iter().map(|i| i.id).collect();
+        let idx_by_id: HashMap<ItemId, usize> =
+            ids.iter().enumerate().map(|(idx, &id)| (id, idx)).collect();
+
+        // Count wins and games between each pair of items.
+        let mut wins = vec![self.prior; ids.len()];
+        let mut games: Vec<HashMap<usize, f64>> = vec![HashMap::new(); ids.len()];
+        for judgment in state.history.iter() {
+            let Ok(pairs) = judgment.pairs() else {
+                continue;
+            };
+            for m in pairs {
+                let (Some(&w), Some(&l)) = (
+                    idx_by_id.get(&judgment.shown[m.winner]),
+                    idx_by_id.get(&judgment.shown[m.loser]),
+                ) else {
+                    continue;
+                };
+                // A draw counts as half a win for each side.
+                if m.draw {
+                    wins[w] += 0.5;
+                    wins[l] += 0.5;
+                } else {
+                    wins[w] += 1.;
+                }
+                *games[w].entry(l).or_default() += 1.;
+                *games[l].entry(w).or_default() += 1.;
+            }
+        }
+
+        // Hunter's minorization-maximization algorithm. The virtual opponent
+        // has a strength of one.
+        let mut strengths = vec![1_f64; ids.len()];
+        for _ in 0..self.max_iterations {
+            let mut next = Vec::with_capacity(ids.len());
+            for (i, opponents) in games.iter().enumerate() {
+                let mut denominator = 2. * self.prior / (strengths[i] + 1.);
+                for (&j, &n) in opponents {
+                    denominator += n / (strengths[i] + strengths[j]);
+                }
+                next.push(wins[i] / denominator);
+            }
+            let max_change = strengths
+                .iter()
+                .zip(next.iter())
+                .map(|(old, new)| (new.ln() - old.ln()).abs())
+                .fold(0., f64::max);
+            strengths = next;
+            if max_change < self.tolerance {
+                break;
+            }
+        }
+
+        let mut scores: Vec<(ItemId, f64)> = ids
+            .into_iter()
+            .zip(strengths.into_iter().map(f64::ln))
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scores)
+    }
+}
@@ -0,0 +1,72 @@
+use std::{error::Error, fmt::Display};
+
+use crate::ItemId;
+
+/// Everything that can go wrong when working with a humansort state.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum HumansortError {
+    /// No item has this ID. It may have been removed.
+    ItemNotFound(ItemId),
+    /// No item has this text.
+    ValueNotFound(String),
+    /// There are fewer items than something needs.
+    NotEnoughItems {
+        needed: usize,
+        found: usize,
+    },
+    /// Prompts have to show at least two items.
+    InvalidNumItems(usize),
+    /// Another item already has this text.
+    DuplicateItem(String),
+    /// A judgment doesn't make sense for the items it was made about.
+    InvalidJudgment(String),
+    /// A rating model's parameters would give nonsense ratings.
+    InvalidModel(String),
+    /// A rating has to be a finite number.
+    InvalidRating(f32),
+    NothingToUndo,
+    NothingToRedo,
+    /// The file was saved by a newer version of humansort, whose format this
+    /// version doesn't know.
+    NewerFormat {
+        version: u64,
+        supported: u64,
+    },
+    /// The file couldn't be read.
+    InvalidFile(String),
+}
+
+impl Display for HumansortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HumansortError::ItemNotFound(id) => write!(f, "Failed to find item {}", id),
+            HumansortError::ValueNotFound(value) => write!(f, "Failed to find '{}'", value),
+            HumansortError::NotEnoughItems { needed, found } => {
+                write!(f, "Not enough items: need {} but found {}", needed, found)
+            }
+            HumansortError::InvalidNumItems(n) => {
+                write!(f, "Number of items to display must be >= 2 (got {})", n)
+            }
+            HumansortError::DuplicateItem(value) => {
+                write!(f, "There's already an item called '{}'", value)
+            }
+            HumansortError::InvalidJudgment(reason) => write!(f, "Invalid judgment: {}", reason),
+            HumansortError::InvalidModel(reason) => write!(f, "Invalid rating model: {}", reason),
+            HumansortError::InvalidRating(rating) => {
+                write!(f, "Ratings must be finite numbers (got {})", rating)
+            }
+            HumansortError::NothingToUndo => write!(f, "Nothing to undo"),
+            HumansortError::NothingToRedo => write!(f, "Nothing to redo"),
+            HumansortError::NewerFormat { version, supported } => write!(
+                f,
+                "This file is from a newer version of humansort (format {}, but only formats up \
+                 to {} are supported). Please upgrade humansort to open it",
+                version, supported
+            ),
+            HumansortError::InvalidFile(reason) => write!(f, "Invalid humansort file: {}", reason),
+        }
+    }
+}
+
+impl Error for HumansortError {}
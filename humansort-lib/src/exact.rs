@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{HumansortState, ItemId, Judgment};
+
+/// Put every item in an exact order by asking the user to compare two items
+/// at a time, with no guesswork from a rating model. This is binary insertion
+/// sort: items are taken in the order they were added, and each one is placed
+/// among the ones already sorted by halving the range where it could go, so
+/// placing the `k`th item takes at most `ceil(log2(k))` questions.
+///
+/// The answers come from the history, so judgments the user already made
+/// count, stopping and resuming needs nothing beyond what's already saved, and
+/// undoing a judgment takes back its answer. When the history has answered
+/// the same question more than once, the latest answer wins.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ExactSort;
+
+/// Where an exact sort has got to. See [`ExactSort::step`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExactStep {
+    /// The user needs to say which of these two items is better.
+    Compare([ItemId; 2]),
+    /// Every item is in place, best first.
+    Done(Vec<ItemId>),
+}
+
+impl ExactSort {
+    /// Replay the sort as far as the history allows.
+    pub fn step(&self, state: &HumansortState) -> ExactStep {
+        let progress = self.progress(state);
+        match progress.question {
+            Some(pair) => ExactStep::Compare(pair),
+            None => ExactStep::Done(progress.sorted),
+        }
+    }
+    /// The most questions it could take to finish the sort. Answers the user
+    /// has already given elsewhere can make it take fewer.
+    pub fn questions_left(&self, state: &HumansortState) -> usize {
+        self.progress(state).questions_left
+    }
+    fn progress(&self, state: &HumansortState) -> Progress {
+        let mut ids: Vec<ItemId> = state.items.iter().map(|item| item.id).collect();
+        ids.sort_unstable();
+        binary_insertion(Vec::new(), &ids, &answers(&state.history))
+    }
+}
+
+/// How far binary insertion got with the answers it had.
+pub(crate) struct Progress {
+    /// The items placed so far, best first.
+    pub sorted: Vec<ItemId>,
+    /// The question that needs answering next, with the item being placed
+    /// first, or `None` if every item is in place.
+    pub question: Option<[ItemId; 2]>,
+    /// The most questions it could take to place the rest of the items.
+    pub questions_left: usize,
+}
+
+/// Place each of `to_place` in turn into `sorted`, which has to be best first,
+/// until a question comes up that `answers` doesn't cover.
+pub(crate) fn binary_insertion(
+    mut sorted: Vec<ItemId>,
+    to_place: &[ItemId],
+    answers: &HashMap<(ItemId, ItemId), bool>,
+) -> Progress {
+    for (k, &id) in to_place.iter().enumerate() {
+        // Narrow down where the item goes by halving the range it could be
+        // in.
+        let (mut lo, mut hi) = (0, sorted.len());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match answers.get(&(id, sorted[mid])) {
+                Some(true) => hi = mid,
+                Some(false) => lo = mid + 1,
+                None => {
+                    let questions_left = questions_to_place(hi - lo)
+                        + (k + 1..to_place.len())
+                            .map(|j| questions_to_place(sorted.len() + j - k))
+                            .sum::<usize>();
+                    return Progress {
+                        question: Some([id, sorted[mid]]),
+                        sorted,
+                        questions_left,
+                    };
+                }
+            }
+        }
+        sorted.insert(lo, id);
+    }
+    Progress {
+        sorted,
+        question: None,
+        questions_left: 0,
+    }
+}
+
+/// The most questions that binary insertion needs to place an item among `n`
+/// others: `ceil(log2(n + 1))`.
+fn questions_to_place(n: usize) -> usize {
+    (usize::BITS - n.leading_zeros()) as usize
+}
+
+/// Every pairwise answer in `history`, keyed both ways round, where `true`
+/// means the first item won. Ties and skips don't answer anything.
+pub(crate) fn answers(history: &[Judgment]) -> HashMap<(ItemId, ItemId), bool> {
+    let mut answers = HashMap::new();
+    for judgment in history {
+        add_answers(&mut answers, judgment);
+    }
+    answers
+}
+
+/// Add the answers in one more judgment, overriding any earlier ones.
+pub(crate) fn add_answers(answers: &mut HashMap<(ItemId, ItemId), bool>, judgment: &Judgment) {
+    // Judgments that no longer make sense are left out, as in a replay.
+    for matchup in judgment.pairs().unwrap_or_default() {
+        if matchup.draw {
+            continue;
+        }
+        let winner = judgment.shown[matchup.winner];
+        let loser = judgment.shown[matchup.loser];
+        answers.insert((winner, loser), true);
+        answers.insert((loser, winner), false);
+    }
+}
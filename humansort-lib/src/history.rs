@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    default_deviation, default_volatility, HumansortError, HumansortItem, ItemId, Matchup,
+};
+
+/// A record of one decision the user made about a set of items. Items are
+/// referred to by their IDs, so renaming an item doesn't change its history.
+/// (Files from before items had IDs refer to them by value instead, which is
+/// why the type of reference can vary.)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Judgment<T = ItemId> {
+    /// The items that were shown, in the order they were shown.
+    pub shown: Vec<T>,
+    pub outcome: Outcome<T>,
+    /// When the judgment was made, in milliseconds since the Unix epoch. A
+    /// state with a seed counts judgments here instead, starting from 0, so
+    /// that the same answers always give the same file.
+    pub timestamp: u64,
+    /// The items this judgment changed, as they were just before it was
+    /// applied. Used to undo it.
+    #[serde(default = "Vec::new")]
+    pub(crate) before: Vec<ItemSnapshot<T>>,
+}
+
+/// The parts of an item that a judgment can change, kept so that the
+/// judgment can be undone. The item's text isn't kept: it can't change with
+/// a judgment, and copying it into every judgment made files much larger.
+/// (Older files did keep whole items here, and the extra fields are ignored.)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct ItemSnapshot<T = ItemId> {
+    pub(crate) id: T,
+    pub(crate) rating: f32,
+    #[serde(default = "default_deviation")]
+    pub(crate) deviation: f32,
+    #[serde(default = "default_volatility")]
+    pub(crate) volatility: f32,
+    #[serde(default)]
+    pub(crate) comparisons: u32,
+    #[serde(default)]
+    pub(crate) appearances: u32,
+}
+
+impl From<&HumansortItem> for ItemSnapshot {
+    fn from(item: &HumansortItem) -> Self {
+        ItemSnapshot {
+            id: item.id,
+            rating: item.rating,
+            deviation: item.deviation,
+            volatility: item.volatility,
+            comparisons: item.comparisons,
+            appearances: item.appearances,
+        }
+    }
+}
+
+impl<T> Judgment<T> {
+    /// Change how the judgment refers to items.
+    pub(crate) fn map_items<U>(self, mut f: impl FnMut(T) -> U) -> Judgment<U> {
+        let outcome = match self.outcome {
+            Outcome::Winner { item } => Outcome::Winner { item: f(item) },
+            Outcome::Ranking { order } => Outcome::Ranking {
+                order: order.into_iter().map(&mut f).collect(),
+            },
+            Outcome::Loser { item } => Outcome::Loser { item: f(item) },
+            Outcome::Tie => Outcome::Tie,
+            Outcome::Abstain => Outcome::Abstain,
+        };
+        Judgment {
+            shown: self.shown.into_iter().map(&mut f).collect(),
+            outcome,
+            timestamp: self.timestamp,
+            before: self
+                .before
+                .into_iter()
+                .map(|old| ItemSnapshot {
+                    id: f(old.id),
+                    rating: old.rating,
+                    deviation: old.deviation,
+                    volatility: old.volatility,
+                    comparisons: old.comparisons,
+                    appearances: old.appearances,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Judgment {
+    /// Every pairwise outcome implied by this judgment. The matchups refer to
+    /// positions in `shown`.
+    pub fn pairs(&self) -> Result<Vec<Matchup>, HumansortError> {
+        match &self.outcome {
+            Outcome::Winner { item } => {
+                let winner = self.shown.iter().position(|s| s == item).ok_or_else(|| {
+                    HumansortError::InvalidJudgment(format!(
+                        "winner {} was not one of the items shown",
+                        item
+                    ))
+                })?;
+                Ok((0..self.shown.len())
+                    .filter(|&loser| loser != winner)
+                    .map(|loser| Matchup::win(winner, loser))
+                    .collect())
+            }
+            Outcome::Loser { item } => {
+                let loser = self.shown.iter().position(|s| s == item).ok_or_else(|| {
+                    HumansortError::InvalidJudgment(format!(
+                        "loser {} was not one of the items shown",
+                        item
+                    ))
+                })?;
+                Ok((0..self.shown.len())
+                    .filter(|&winner| winner != loser)
+                    .map(|winner| Matchup::win(winner, loser))
+                    .collect())
+            }
+            Outcome::Tie => {
+                let mut pairs = Vec::new();
+                for a in 0..self.shown.len() {
+                    for b in a + 1..self.shown.len() {
+                        pairs.push(Matchup::draw(a, b));
+                    }
+                }
+                Ok(pairs)
+            }
+            Outcome::Abstain => Ok(Vec::new()),
+            Outcome::Ranking { order } => {
+                if order.is_empty() {
+                    return Err(HumansortError::InvalidJudgment(
+                        "a ranking must contain at least one item".to_string(),
+                    ));
+                }
+                let mut ranked = Vec::new();
+                for item in order {
+                    let pos = self.shown.iter().position(|s| s == item).ok_or_else(|| {
+                        HumansortError::InvalidJudgment(format!(
+                            "ranked item {} was not one of the items shown",
+                            item
+                        ))
+                    })?;
+                    if ranked.contains(&pos) {
+                        return Err(HumansortError::InvalidJudgment(format!(
+                            "{} was ranked more than once",
+                            item
+                        )));
+                    }
+                    ranked.push(pos);
+                }
+                // Every ranked item beats the ones ranked below it and all of
+                // the unranked ones. Unranked items aren't compared with each
+                // other.
+                let unranked: Vec<usize> = (0..self.shown.len())
+                    .filter(|pos| !ranked.contains(pos))
+                    .collect();
+                let mut pairs = Vec::new();
+                for (i, &winner) in ranked.iter().enumerate() {
+                    for &loser in ranked[i + 1..].iter().chain(unranked.iter()) {
+                        pairs.push(Matchup::win(winner, loser));
+                    }
+                }
+                Ok(pairs)
+            }
+        }
+    }
+}
+
+/// What the user decided about the items in a [`Judgment`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Outcome<T = ItemId> {
+    /// One item was preferred over all the others.
+    Winner { item: T },
+    /// Some or all of the items were put in order, best first. Items left out
+    /// rank below every item in `order`.
+    Ranking { order: Vec<T> },
+    /// One item was judged worse than all the others.
+    Loser { item: T },
+    /// All of the items were judged equally good.
+    Tie,
+    /// The user couldn't compare the items. Nothing changes, but the fact
+    /// that they were shown is kept.
+    Abstain,
+}
+
+/// Milliseconds since the Unix epoch. `SystemTime` isn't available in the
+/// browser, so ask JavaScript there instead.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,130 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    exact::{self, Progress},
+    HumansortState, ItemId,
+};
+
+/// Place a few new items into a list that's already well sorted, asking
+/// about `log2(n)` questions each instead of waiting for random prompts to
+/// find the right spot for them. Each new item is compared with items at
+/// chosen ranks, halving the range where it could go every time, as in
+/// [`ExactSort`](crate::ExactSort). Once it's in place, it gets a rating
+/// halfway between its neighbors'.
+///
+/// The ranking that items are placed into is fixed when the session starts,
+/// and items placed earlier in the session count as part of it. Answers are
+/// kept in the history, but they don't move any ratings by themselves, so
+/// that the ranking stays put while new items are measured against it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Insertion {
+    /// The items to place, in the order they'll be placed.
+    pub items: Vec<ItemId>,
+    /// Every other item, best first, as of when the session started.
+    pub ranking: Vec<ItemId>,
+}
+
+impl Insertion {
+    /// Get ready to place `items` into the rest of the state's ranking.
+    pub fn new(state: &HumansortState, items: &[ItemId]) -> Self {
+        Insertion {
+            items: items.to_vec(),
+            ranking: state
+                .items
+                .iter()
+                .map(|item| item.id)
+                .filter(|id| !items.contains(id))
+                .collect(),
+        }
+    }
+    /// The items to show next, or `None` once every item is in place.
+    pub fn next(&self, state: &HumansortState) -> Option<[ItemId; 2]> {
+        self.progress(state, &exact::answers(&state.history))
+            .question
+    }
+    /// The most questions it could take to place the rest of the items.
+    pub fn questions_left(&self, state: &HumansortState) -> usize {
+        self.progress(state, &exact::answers(&state.history))
+            .questions_left
+    }
+    /// Undo what the rating model did with the latest judgment, and give any
+    /// item that it put in place a rating between the items on either side of
+    /// it.
+    pub(crate) fn settle(&self, state: &mut HumansortState) {
+        let Some((latest, earlier)) = state.history.split_last() else {
+            return;
+        };
+        let before = latest.before.clone();
+        let mut answers = exact::answers(earlier);
+        let placed_before: HashSet<ItemId> =
+            self.progress(state, &answers).sorted.into_iter().collect();
+        exact::add_answers(&mut answers, latest);
+        let sorted = self.progress(state, &answers).sorted;
+        for old in before.iter() {
+            state.restore_rating(old);
+        }
+
+        // Only items that this answer touched can have just been put in
+        // place. An answer that contradicts an earlier one can also take
+        // items out of place, so this is a difference, not a count.
+        let just_placed: HashSet<ItemId> = before
+            .iter()
+            .map(|old| old.id)
+            .filter(|id| self.items.contains(id) && !placed_before.contains(id))
+            .collect();
+        if !sorted.iter().any(|id| just_placed.contains(id)) {
+            return;
+        }
+
+        // Rate them against everything else in place, which doesn't include
+        // their own ratings from before they were placed.
+        let rest: Vec<f32> = sorted
+            .iter()
+            .filter(|id| !just_placed.contains(id))
+            .map(|&id| state.rating_of(id).unwrap_or_default())
+            .collect();
+        // Past either end, step out as far as the average gap in the ranking.
+        let gap = match rest.len() {
+            0 | 1 => 0.,
+            n => (rest[0] - rest[n - 1]) / (n - 1) as f32,
+        };
+        let mut placed = Vec::new();
+        // How many of the rest are ranked above the item.
+        let mut above = 0_usize;
+        for &id in sorted.iter() {
+            if !just_placed.contains(&id) {
+                above += 1;
+                continue;
+            }
+            let interpolated = match (above.checked_sub(1), rest.get(above)) {
+                (Some(a), Some(b)) => (rest[a] + b) / 2.,
+                (None, Some(b)) => b + gap,
+                (Some(a), None) => rest[a] - gap,
+                (None, None) => continue,
+            };
+            placed.push((id, interpolated));
+        }
+        for (id, rating) in placed {
+            state.set_rating(id, rating);
+        }
+    }
+    /// How far the placement gets with `answers`.
+    fn progress(
+        &self,
+        state: &HumansortState,
+        answers: &HashMap<(ItemId, ItemId), bool>,
+    ) -> Progress {
+        // Items that have been removed since the session started are left
+        // out.
+        let ranking = present(state, &self.ranking).collect();
+        let items: Vec<ItemId> = present(state, &self.items).collect();
+        exact::binary_insertion(ranking, &items, answers)
+    }
+}
+
+/// The items in `ids` that are still in the list.
+fn present<'a>(state: &'a HumansortState, ids: &'a [ItemId]) -> impl Iterator<Item = ItemId> + 'a {
+    ids.iter().filter(|id| state.item(**id).is_some()).copied()
+}
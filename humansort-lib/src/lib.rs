@@ -1,15 +1,124 @@
-use std::{collections::HashSet, error::Error, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
 
-use rand::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, RngCore, SeedableRng};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub mod bradley_terry;
+mod error;
+pub mod exact;
+pub mod history;
+pub mod insertion;
+pub mod model;
+mod rename;
+pub mod selection;
+pub mod session;
+mod storage;
+pub mod tournament;
+
+pub use bradley_terry::BradleyTerry;
+pub use error::HumansortError;
+pub use exact::{ExactSort, ExactStep};
+pub use history::{Judgment, Outcome};
+pub use insertion::Insertion;
+pub use model::{Elo, Glicko2, Matchup, Model, RatingModel};
+pub use rename::{similarity, RenameCandidate};
+pub use selection::{ActiveLearning, Quadratic, SelectionStrategy, Strategy, TopK};
+pub use session::Session;
+pub use storage::FORMAT_VERSION;
+pub use tournament::{Bracket, RoundRobin, Standing, Swiss, TournamentProgress};
+
+use history::ItemSnapshot;
+
+/// The state of a sorting session. How it's saved, and how older files are
+/// upgraded, is up to `storage`.
+#[derive(Clone, PartialEq)]
 pub struct HumansortState {
     items: Vec<HumansortItem>,
-    #[serde(skip, default = "default_num_items")]
     num_items: usize,
-    #[serde(skip, default = "default_current_idx")]
     current_idx: usize,
+    model: Model,
+    selection: Strategy,
+    history: Vec<Judgment>,
+    redo: Vec<Judgment>,
+    seed: Option<u64>,
+    min_appearances: u32,
+    question: Option<String>,
+    archived: Vec<HumansortItem>,
+    session: Option<Session>,
+    /// The last ID given to an item. IDs are never reused, so that the history
+    /// can't mistake a new item for one that was removed.
+    next_id: u64,
+    /// Where each item is in `items`, which is kept sorted descending by
+    /// rating.
+    positions: HashMap<ItemId, usize>,
+    ids_by_value: HashMap<String, ItemId>,
+}
+
+/// A stable identifier for an item, which stays the same when the item is
+/// renamed or the list is reordered.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[serde(transparent)]
+pub struct ItemId(u64);
+
+impl Display for ItemId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A set of items to show the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prompt {
+    pub items: Vec<ItemId>,
+    /// See [`HumansortState::convergence`].
+    pub convergence: f32,
+}
+
+/// An item along with its place in the ranking. See
+/// [`HumansortState::ranked`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RankedItem<'a> {
+    /// The item's position in the ranking, starting from 1 for the best item.
+    /// Items with equal ratings get consecutive ranks.
+    pub rank: usize,
+    pub id: ItemId,
+    pub value: &'a str,
+    pub rating: f32,
+    /// How far off the rating might be, in rating points, if the model keeps
+    /// track of that.
+    pub uncertainty: Option<f32>,
+    /// How many pairwise comparisons the item has been part of.
+    pub comparisons: u32,
+}
+
+/// How [`HumansortState::merge_with`] treats the items it adds and removes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeOptions {
+    /// Keep items that are missing from the new list out of the way instead
+    /// of deleting them, so that they come back with their ratings if they're
+    /// added again.
+    pub archive_missing: bool,
+    /// Where new items start.
+    pub new_rating: NewRating,
+}
+
+/// The rating that new items start with when they're merged in.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NewRating {
+    /// The same rating as every item in a new list.
+    #[default]
+    Unrated,
+    /// The mean rating of the items already in the list.
+    Mean,
+    /// The median rating of the items already in the list.
+    Median,
+    /// A rating chosen by the user.
+    Prior(f32),
 }
 
 fn default_num_items() -> usize {
@@ -20,106 +129,490 @@ fn default_current_idx() -> usize {
     0
 }
 
+fn default_min_appearances() -> u32 {
+    3
+}
+
 impl HumansortState {
     pub fn new() -> Self {
         HumansortState::default()
     }
-    pub fn next(&self) -> Result<Vec<String>, Box<dyn Error>> {
-        // Select the desired number of items with a preference for higher-rated
-        // items. (This avoids prompting the user for more information on items
-        // that they rated lower already.)
+    /// Pick the next items to show. If the state has a seed, the choice only
+    /// depends on the seed and the number of judgments made so far, so the
+    /// same answers always lead to the same prompts.
+    pub fn next(&self) -> Result<Vec<ItemId>, HumansortError> {
+        match self.seed {
+            Some(seed) => {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(self.history.len() as u64));
+                self.next_with_rng(&mut rng)
+            }
+            None => self.next_with_rng(&mut rand::thread_rng()),
+        }
+    }
+    /// Pick the next items to show using the given source of randomness.
+    pub fn next_with_rng(&self, rng: &mut dyn RngCore) -> Result<Vec<ItemId>, HumansortError> {
         if self.items.len() < self.num_items {
-            return Err(format!(
-                "Not enough items: need {} but found {}",
-                self.num_items,
-                self.items.len()
-            )
-            .into());
-        }
-        let mut rng = rand::thread_rng();
-        let mut indices = Vec::new();
-        while indices.len() < self.num_items {
-            let x = rng.gen_range(0_f32..1_f32);
-            let y = x.powi(2);
-            let y_rounded = (y * self.items.len() as f32).floor() as usize;
-            if !indices.contains(&y_rounded) {
-                indices.push(y_rounded);
+            return Err(HumansortError::NotEnoughItems {
+                needed: self.num_items,
+                found: self.items.len(),
+            });
+        }
+        let mut indices = self
+            .selection
+            .select(&self.items, &self.model, self.num_items, rng);
+
+        // Make sure that items which haven't been shown enough yet, such as
+        // ones that were just added, get a turn no matter what the strategy
+        // thinks. Up to half of the prompt goes to them, fewest appearances
+        // first, so that they're still compared against known items.
+        let is_due = |idx: usize| self.items[idx].appearances < self.min_appearances;
+        let mut due: Vec<usize> = (0..self.items.len())
+            .filter(|&idx| is_due(idx) && !indices.contains(&idx))
+            .collect();
+        due.shuffle(rng);
+        due.sort_by_key(|&idx| self.items[idx].appearances);
+        let wanted = (self.num_items / 2)
+            .max(1)
+            .saturating_sub(indices.iter().filter(|&&idx| is_due(idx)).count());
+        let replaceable: Vec<usize> = (0..indices.len())
+            .rev()
+            .filter(|&slot| !is_due(indices[slot]))
+            .collect();
+        for (slot, idx) in replaceable.into_iter().zip(due.into_iter().take(wanted)) {
+            indices[slot] = idx;
+        }
+
+        Ok(indices.into_iter().map(|idx| self.items[idx].id).collect())
+    }
+    /// Pick the next items to show, along with how settled the ranking is.
+    pub fn prompt(&self) -> Result<Prompt, HumansortError> {
+        Ok(Prompt {
+            items: self.next()?,
+            convergence: self.convergence(),
+        })
+    }
+    /// Roughly how settled the ranking is, from 0 to 1, judged by how much it
+    /// has changed recently. This compares every item's current rank with its
+    /// rank before the last few judgments; a ranking that reshuffles as much
+    /// as a random permutation would scores 0, and one that didn't move at all
+    /// scores 1.
+    pub fn convergence(&self) -> f32 {
+        let n = self.items.len();
+        if n < 2 {
+            return 1.;
+        }
+        if self.history.is_empty() {
+            return 0.;
+        }
+        let past = self.past_ratings(self.lookback_window());
+
+        let ranks = |rating_of: &dyn Fn(&HumansortItem) -> f32| {
+            let mut order: Vec<&HumansortItem> = self.items.iter().collect();
+            order.sort_by(|a, b| {
+                rating_of(b)
+                    .partial_cmp(&rating_of(a))
+                    .unwrap()
+                    .then_with(|| a.value.cmp(&b.value))
+            });
+            order
+                .into_iter()
+                .enumerate()
+                .map(|(rank, item)| (item.id, rank))
+                .collect::<HashMap<_, _>>()
+        };
+        let now = ranks(&|i| i.rating);
+        let before = ranks(&|i| past[&i.id]);
+
+        // Compare the mean displacement with what a random shuffle would give.
+        let displacement: usize = now
+            .iter()
+            .map(|(id, &rank)| rank.abs_diff(before[id]))
+            .sum();
+        let mean_displacement = displacement as f32 / n as f32;
+        (1. - mean_displacement / (n as f32 / 3.)).max(0.)
+    }
+    /// Whether the set of the `k` highest-rated items looks settled: the
+    /// model is fairly sure which side of the cutoff every item belongs on
+    /// (see [`TopK::membership_uncertainty`]), the same items have made up the
+    /// top `k` after each of the last few judgments, and the answers show
+    /// every item below the cutoff losing to at least `k` others. So unless
+    /// the answers contradict each other, a settled set is the right one. The
+    /// order within the set doesn't matter.
+    pub fn top_k_settled(&self, k: usize) -> bool {
+        if k == 0 || k >= self.items.len() {
+            return true;
+        }
+        let window = self.lookback_window();
+        if self.history.len() <= window {
+            return false;
+        }
+        let uncertainty = TopK { k }.membership_uncertainty(&self.items, &self.model);
+        if uncertainty.into_iter().any(|u| u >= 0.5) {
+            return false;
+        }
+        // The model can be sure of itself and still be wrong about items it
+        // has seen little of, so ask the answers too: every item below the
+        // cutoff has to have lost to at least `k` others, directly or through
+        // a chain of answers.
+        let mut beaten_by: HashMap<ItemId, Vec<ItemId>> = HashMap::new();
+        for (&(winner, loser), &won) in exact::answers(&self.history).iter() {
+            if won && self.positions.contains_key(&winner) {
+                beaten_by.entry(loser).or_default().push(winner);
             }
         }
-        let mut values = Vec::new();
-        for idx in indices {
-            values.push(self.items[idx].value.clone());
+        let shown_to_be_out = |id: ItemId| {
+            let mut better = HashSet::from([id]);
+            let mut to_visit = vec![id];
+            while let Some(next) = to_visit.pop() {
+                for &winner in beaten_by.get(&next).into_iter().flatten() {
+                    if better.insert(winner) {
+                        // `better` includes the item itself.
+                        if better.len() > k {
+                            return true;
+                        }
+                        to_visit.push(winner);
+                    }
+                }
+            }
+            false
+        };
+        if !self.items[k..].iter().all(|item| shown_to_be_out(item.id)) {
+            return false;
         }
-        Ok(values)
+        let top = |ratings: &HashMap<ItemId, f32>| {
+            let mut order: Vec<(ItemId, f32)> = ratings.iter().map(|(&id, &r)| (id, r)).collect();
+            order.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then_with(|| a.0.cmp(&b.0)));
+            order
+                .into_iter()
+                .take(k)
+                .map(|(id, _)| id)
+                .collect::<HashSet<_>>()
+        };
+        // Wind the ratings back one judgment at a time, checking the top `k`
+        // along the way.
+        let mut ratings: HashMap<ItemId, f32> =
+            self.items.iter().map(|i| (i.id, i.rating)).collect();
+        let current = top(&ratings);
+        for judgment in self.history.iter().rev().take(window) {
+            for old in judgment.before.iter() {
+                if let Some(rating) = ratings.get_mut(&old.id) {
+                    *rating = old.rating;
+                }
+            }
+            if top(&ratings) != current {
+                return false;
+            }
+        }
+        true
+    }
+    /// How many judgments to look back over when checking whether the ranking
+    /// has settled: far enough that most items could have been shown once.
+    fn lookback_window(&self) -> usize {
+        10.max(self.items.len() / self.num_items)
+    }
+    /// Every item's rating from before the last `window` judgments, wound
+    /// back using the snapshots that each judgment kept.
+    fn past_ratings(&self, window: usize) -> HashMap<ItemId, f32> {
+        let mut past: HashMap<ItemId, f32> = self.items.iter().map(|i| (i.id, i.rating)).collect();
+        for judgment in self.history.iter().rev().take(window) {
+            for old in judgment.before.iter() {
+                if let Some(rating) = past.get_mut(&old.id) {
+                    *rating = old.rating;
+                }
+            }
+        }
+        past
     }
-    pub fn update(&mut self, new_data: &[String]) -> Result<(), Box<dyn Error>> {
+    pub fn update(&mut self, new_data: &[String]) -> Result<(), HumansortError> {
         // Assume that the first item is the "winner", and all others are the
-        // "losers". Compute rating updates based on pairwise comparisons
-        // between the first item and all the others.
-        if new_data.len() < 2 {
-            return Err(format!("Must have at least two items, found {}", new_data.len()).into());
+        // "losers". The order they were shown in isn't known here, so it's
+        // recorded as-is.
+        if new_data.is_empty() {
+            return Err(HumansortError::NotEnoughItems {
+                needed: 2,
+                found: 0,
+            });
+        }
+        let shown = new_data
+            .iter()
+            .map(|value| self.id_of(value))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.judge(&shown, shown[0])
+    }
+    pub fn judge(&mut self, shown: &[ItemId], winner: ItemId) -> Result<(), HumansortError> {
+        self.record(shown, Outcome::Winner { item: winner })
+    }
+    /// Record a complete or partial ordering of the items shown, best first.
+    /// Every pairwise outcome it implies is passed to the rating model.
+    pub fn rank(&mut self, shown: &[ItemId], order: &[ItemId]) -> Result<(), HumansortError> {
+        self.record(
+            shown,
+            Outcome::Ranking {
+                order: order.to_vec(),
+            },
+        )
+    }
+    /// Record that `loser` was worse than every other item shown.
+    pub fn judge_loser(&mut self, shown: &[ItemId], loser: ItemId) -> Result<(), HumansortError> {
+        self.record(shown, Outcome::Loser { item: loser })
+    }
+    /// Record that every item shown was equally good.
+    pub fn tie(&mut self, shown: &[ItemId]) -> Result<(), HumansortError> {
+        self.record(shown, Outcome::Tie)
+    }
+    /// Record that the user couldn't compare the items shown. No ratings
+    /// change.
+    pub fn abstain(&mut self, shown: &[ItemId]) -> Result<(), HumansortError> {
+        self.record(shown, Outcome::Abstain)
+    }
+    fn record(&mut self, shown: &[ItemId], outcome: Outcome) -> Result<(), HumansortError> {
+        self.apply(Judgment {
+            shown: shown.to_vec(),
+            outcome,
+            timestamp: self.timestamp(),
+            before: Vec::new(),
+        })?;
+        // A new judgment replaces whatever was undone before it.
+        self.redo.clear();
+        Ok(())
+    }
+    /// Take back the most recent judgment, restoring the ratings it changed to
+    /// exactly what they were before.
+    pub fn undo(&mut self) -> Result<Judgment, HumansortError> {
+        let judgment = self.history.pop().ok_or(HumansortError::NothingToUndo)?;
+        let mut restored = Vec::new();
+        for old_item in judgment.before.iter() {
+            // Items that have been removed since don't need restoring.
+            if let Ok(idx) = self.find_item_idx(old_item.id) {
+                let item = &mut self.items[idx];
+                item.rating = old_item.rating;
+                item.deviation = old_item.deviation;
+                item.volatility = old_item.volatility;
+                item.comparisons = old_item.comparisons;
+                item.appearances = old_item.appearances;
+                restored.push(old_item.id);
+            }
+        }
+        self.reposition(&restored);
+        if let Some(session) = self.session.as_mut() {
+            session.truncate_history(self.history.len());
         }
+        self.redo.push(judgment.clone());
+        Ok(judgment)
+    }
+    /// Make the most recently undone judgment again.
+    pub fn redo(&mut self) -> Result<Judgment, HumansortError> {
+        let judgment = self.redo.pop().ok_or(HumansortError::NothingToRedo)?;
+        if let Err(e) = self.apply(judgment.clone()) {
+            // Leave it where it was so that nothing is lost.
+            self.redo.push(judgment);
+            return Err(e);
+        }
+        Ok(judgment)
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    /// Rebuild every rating from scratch by replaying the history under
+    /// `model`, which then becomes the state's model. Judgments that mention
+    /// items which have since been removed are replayed without them, and
+    /// judgments that no longer make sense at all are kept in the history but
+    /// have no effect. A model whose parameters don't make sense is refused
+    /// and nothing changes.
+    pub fn replay(&mut self, model: Model) -> Result<(), HumansortError> {
+        model.validate()?;
+        self.model = model;
+        for item in self.items.iter_mut() {
+            item.reset_rating();
+        }
+        let history = std::mem::take(&mut self.history);
+        for mut judgment in history {
+            let mut surviving = judgment.clone();
+            surviving.shown.retain(|&id| self.find_item_idx(id).is_ok());
+            judgment.before = self.rate(&surviving).unwrap_or_default();
+            self.history.push(judgment);
+        }
+        self.sort_items();
+        Ok(())
+    }
+    fn apply(&mut self, mut judgment: Judgment) -> Result<(), HumansortError> {
+        judgment.before = self.rate(&judgment)?;
+        let shown = judgment.shown.clone();
 
-        let winner = &new_data[0];
-        let losers = &new_data[1..];
+        // Remember what was decided.
+        self.history.push(judgment);
 
-        // Find the winner's rating.
-        let winner_idx = self.find_item_idx_by_value(winner)?;
-        let winner_rating = self.items[winner_idx].rating;
-        let mut winner_rating_increase = 0.;
-        for loser in losers.iter() {
-            // Find the loser's rating.
-            let loser_idx = self.find_item_idx_by_value(loser)?;
-            let loser_rating = self.items[loser_idx].rating;
+        // Let the session in progress react to the answer.
+        if let Some(session) = self.session.clone() {
+            session.settle(self);
+        }
 
-            // Compute the expected score of the winner in the match between the
-            // winner and loser.
-            let expected = 1. / (1. + 10_f32.powf(loser_rating - winner_rating));
+        // Only the items in the judgment changed, so only they need to move.
+        self.reposition(&shown);
 
-            // Update the loser's rating.
-            self.items[loser_idx].rating -= expected;
+        Ok(())
+    }
+    /// Update ratings according to `judgment` and return snapshots of the
+    /// items it touched, as they were beforehand.
+    fn rate(&mut self, judgment: &Judgment) -> Result<Vec<ItemSnapshot>, HumansortError> {
+        let shown = &judgment.shown;
+        if shown.len() < 2 {
+            return Err(HumansortError::NotEnoughItems {
+                needed: 2,
+                found: shown.len(),
+            });
+        }
+        if shown.iter().collect::<HashSet<_>>().len() != shown.len() {
+            return Err(HumansortError::InvalidJudgment(
+                "the items shown must be distinct".to_string(),
+            ));
+        }
+        let indices = shown
+            .iter()
+            .map(|&id| self.find_item_idx(id))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Compute rating updates based on the pairwise comparisons that the
+        // judgment implies.
+        let matchups: Vec<Matchup> = judgment
+            .pairs()?
+            .into_iter()
+            .map(|m| Matchup {
+                winner: indices[m.winner],
+                loser: indices[m.loser],
+                draw: m.draw,
+            })
+            .collect();
+
+        // Keep what this judgment changes so that it can be undone exactly.
+        let before = indices
+            .iter()
+            .map(|&i| ItemSnapshot::from(&self.items[i]))
+            .collect();
 
-            // Add the winner's rating update to a running total.
-            winner_rating_increase += expected;
+        // Let the rating model decide how much each item moves.
+        self.model.update(&mut self.items, &matchups);
+        for &idx in indices.iter() {
+            self.items[idx].appearances += 1;
+        }
+        for m in matchups.iter() {
+            self.items[m.winner].comparisons += 1;
+            self.items[m.loser].comparisons += 1;
         }
-        // Update the winner's rating.
-        self.items[winner_idx].rating += winner_rating_increase;
-        // Sort descending by rating.
-        self.items
-            .sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap());
 
-        Ok(())
+        Ok(before)
     }
+    /// Make the list match `items_to_merge`: items that aren't in it are
+    /// removed, and new ones are added unrated.
     pub fn merge(&mut self, items_to_merge: &[String]) {
-        let mut new_items = Vec::new();
-        // Keep items that are in the new list. (This implicitly removes items
-        // that are in the old list by not the new list.)
-        for item in self.items.iter() {
-            if items_to_merge.contains(&item.value) {
-                new_items.push(item.clone());
+        // The default options always make sense.
+        let _ = self.merge_with(items_to_merge, &MergeOptions::default());
+    }
+    /// Like [`HumansortState::merge`], but with control over what happens to
+    /// missing items and where new ones start. Returns the IDs of the items
+    /// that are new to the list, not counting archived ones that came back. A
+    /// prior that isn't a finite number is refused and nothing changes.
+    pub fn merge_with(
+        &mut self,
+        items_to_merge: &[String],
+        options: &MergeOptions,
+    ) -> Result<Vec<ItemId>, HumansortError> {
+        if let NewRating::Prior(rating) = options.new_rating {
+            if !rating.is_finite() {
+                return Err(HumansortError::InvalidRating(rating));
             }
         }
-        // Add items that are in the new list but not already in the old list.
-        for item in items_to_merge {
-            if !self.items.iter().any(|i| i.value == *item) {
-                new_items.push(HumansortItem {
-                    value: item.clone(),
-                    rating: 0.,
-                });
+        let wanted: HashSet<&str> = items_to_merge.iter().map(|s| s.as_str()).collect();
+        // Keep items that are in the new list, and archive or drop the rest.
+        let (kept, missing): (Vec<_>, Vec<_>) = std::mem::take(&mut self.items)
+            .into_iter()
+            .partition(|item| wanted.contains(item.value.as_str()));
+        self.items = kept;
+        self.reindex();
+        let mut archive: HashMap<String, HumansortItem> = std::mem::take(&mut self.archived)
+            .into_iter()
+            .map(|item| (item.value.clone(), item))
+            .collect();
+        if options.archive_missing {
+            // A newer item with the same text replaces an older archived one.
+            archive.extend(missing.into_iter().map(|item| (item.value.clone(), item)));
+        }
+
+        // Add items that are in the new list but not already in the old list,
+        // bringing back archived ones as they were.
+        let new_rating = self.new_item_rating(options.new_rating);
+        let mut added = Vec::new();
+        for value in items_to_merge {
+            if self.ids_by_value.contains_key(value) {
+                continue;
             }
+            let item = match archive.remove(value) {
+                Some(item) => item,
+                None => {
+                    let id = self.new_id();
+                    added.push(id);
+                    HumansortItem {
+                        id,
+                        value: value.clone(),
+                        rating: new_rating,
+                        ..Default::default()
+                    }
+                }
+            };
+            self.ids_by_value.insert(value.clone(), item.id);
+            self.items.push(item);
         }
-        self.items = new_items;
-        // Sort descending by rating.
-        self.items
-            .sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap());
+        self.archived = archive.into_values().collect();
+        self.archived.sort_by_key(|item| item.id);
+        self.sort_items();
+        Ok(added)
+    }
+    /// Guess which items that are missing from `items_to_merge` were renamed
+    /// to which of its new lines, most similar first. Each item and line
+    /// appears at most once, and pairs less similar than `min_similarity` are
+    /// left out.
+    pub fn rename_candidates(
+        &self,
+        items_to_merge: &[String],
+        min_similarity: f32,
+    ) -> Vec<RenameCandidate> {
+        let wanted: HashSet<&str> = items_to_merge.iter().map(|s| s.as_str()).collect();
+        let missing: Vec<(ItemId, &str)> = self
+            .items
+            .iter()
+            .filter(|item| !wanted.contains(item.value.as_str()))
+            .map(|item| (item.id, item.value.as_str()))
+            .collect();
+        let mut seen = HashSet::new();
+        let added: Vec<&str> = items_to_merge
+            .iter()
+            .map(|s| s.as_str())
+            .filter(|value| !self.ids_by_value.contains_key(*value) && seen.insert(*value))
+            .collect();
+        rename::pair_renames(&missing, &added, min_similarity)
+    }
+    /// Items that were left out of a merge with
+    /// [`MergeOptions::archive_missing`] set, oldest first.
+    pub fn archived(&self) -> &[HumansortItem] {
+        &self.archived
     }
-    pub fn set_num_items(&mut self, new_num_items: usize) -> Result<(), Box<dyn Error>> {
+    fn new_item_rating(&self, new_rating: NewRating) -> f32 {
+        let ratings: Vec<f32> = self.items.iter().map(|item| item.rating).collect();
+        let n = ratings.len();
+        match new_rating {
+            NewRating::Mean if n > 0 => ratings.iter().sum::<f32>() / n as f32,
+            // The items are already in order.
+            NewRating::Median if n > 0 => (ratings[(n - 1) / 2] + ratings[n / 2]) / 2.,
+            NewRating::Prior(rating) => rating,
+            _ => HumansortItem::default().rating,
+        }
+    }
+    pub fn set_num_items(&mut self, new_num_items: usize) -> Result<(), HumansortError> {
         if new_num_items < 2 {
-            return Err(format!(
-                "Number of items to display must be >= 2 (got {})",
-                new_num_items
-            )
-            .into());
+            return Err(HumansortError::InvalidNumItems(new_num_items));
         }
         self.num_items = new_num_items;
         Ok(())
@@ -127,39 +620,247 @@ impl HumansortState {
     pub fn num_items(&self) -> usize {
         self.num_items
     }
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+    pub fn set_model(&mut self, new_model: Model) {
+        self.model = new_model;
+    }
+    pub fn selection(&self) -> &Strategy {
+        &self.selection
+    }
+    pub fn set_selection(&mut self, new_selection: Strategy) {
+        self.selection = new_selection;
+    }
+    /// How many times every item should be shown before the selection
+    /// strategy gets to choose freely.
+    pub fn min_appearances(&self) -> u32 {
+        self.min_appearances
+    }
+    pub fn set_min_appearances(&mut self, new_min_appearances: u32) {
+        self.min_appearances = new_min_appearances;
+    }
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+    /// Make prompts depend only on `new_seed` and the history, so that the
+    /// same answers lead to the same prompts and ratings. Judgments made
+    /// while there's a seed are numbered instead of timed, so the same
+    /// answers also give the same file.
+    pub fn set_seed(&mut self, new_seed: Option<u64>) {
+        self.seed = new_seed;
+    }
+    /// The question to ask when showing items, if the user chose one.
+    pub fn question(&self) -> Option<&str> {
+        self.question.as_deref()
+    }
+    pub fn set_question(&mut self, new_question: Option<String>) {
+        self.question = new_question;
+    }
+    /// The structured session in progress, if there is one.
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+    /// Start a structured session, replacing any that's in progress.
+    pub fn start_session(&mut self, new_session: Session) {
+        self.session = Some(new_session);
+    }
+    pub fn end_session(&mut self) {
+        self.session = None;
+    }
+    /// The items that the session in progress wants to show next, or `None`
+    /// if there's no session or it's over.
+    pub fn session_prompt(&self) -> Option<Vec<ItemId>> {
+        self.session.as_ref()?.next(self)
+    }
+    /// Every judgment made so far, oldest first.
+    pub fn history(&self) -> &[Judgment] {
+        &self.history
+    }
+    /// A copy of every item, from best to worst. [`HumansortState::ranked`]
+    /// does the same without copying.
     pub fn get_all_items(&self) -> Vec<HumansortItem> {
         self.items.clone()
     }
-    pub fn add_item(&mut self, new_item: &String) {
+    /// Every item from best to worst, without copying any of them.
+    pub fn ranked(&self) -> impl ExactSizeIterator<Item = RankedItem<'_>> + '_ {
+        (0..self.items.len()).map(|idx| self.ranked_item(idx))
+    }
+    /// Where the item with the given ID is in the ranking, starting from 1.
+    pub fn rank_of(&self, id: ItemId) -> Option<usize> {
+        self.positions.get(&id).map(|&idx| idx + 1)
+    }
+    pub fn rating_of(&self, id: ItemId) -> Option<f32> {
+        self.item(id).map(|item| item.rating)
+    }
+    /// Up to `n` items ranked just above the given one, followed by up to `n`
+    /// items ranked just below it, in order. The item itself isn't included.
+    pub fn neighbors(&self, id: ItemId, n: usize) -> Option<Vec<RankedItem<'_>>> {
+        let idx = *self.positions.get(&id)?;
+        let above = idx.saturating_sub(n)..idx;
+        let below = idx + 1..(idx + 1 + n).min(self.items.len());
+        Some(
+            above
+                .chain(below)
+                .map(|idx| self.ranked_item(idx))
+                .collect(),
+        )
+    }
+    fn ranked_item(&self, idx: usize) -> RankedItem<'_> {
+        let item = &self.items[idx];
+        RankedItem {
+            rank: idx + 1,
+            id: item.id,
+            value: &item.value,
+            rating: item.rating,
+            uncertainty: self.model.uncertainty(item),
+            comparisons: item.comparisons,
+        }
+    }
+    /// The item with the given ID, if it's still in the list.
+    pub fn item(&self, id: ItemId) -> Option<&HumansortItem> {
+        self.positions.get(&id).map(|&idx| &self.items[idx])
+    }
+    /// The ID of the item whose text is `value`.
+    pub fn id_of(&self, value: &str) -> Result<ItemId, HumansortError> {
+        self.ids_by_value
+            .get(value)
+            .copied()
+            .ok_or_else(|| HumansortError::ValueNotFound(value.to_string()))
+    }
+    /// Add a new, unrated item. Two items can't have the same text, since
+    /// text is how users tell them apart.
+    pub fn add_item(&mut self, new_item: &str) -> Result<ItemId, HumansortError> {
+        if self.ids_by_value.contains_key(new_item) {
+            return Err(HumansortError::DuplicateItem(new_item.to_string()));
+        }
+        let id = self.new_id();
+        self.ids_by_value.insert(new_item.to_string(), id);
+        self.positions.insert(id, self.items.len());
         self.items.push(HumansortItem {
+            id,
             value: new_item.to_string(),
             ..Default::default()
-        })
+        });
+        self.reposition(&[id]);
+        Ok(id)
     }
-    pub fn rename_item(
-        &mut self,
-        old_item_name: &String,
-        new_item_name: &String,
-    ) -> Result<(), Box<dyn Error>> {
-        let item_idx = self.find_item_idx_by_value(old_item_name)?;
-        self.items[item_idx].value = new_item_name.to_string();
+    /// Change an item's text. Its ratings and history stay the same.
+    pub fn rename_item(&mut self, id: ItemId, new_item_name: &str) -> Result<(), HumansortError> {
+        let item_idx = self.find_item_idx(id)?;
+        match self.ids_by_value.get(new_item_name) {
+            Some(&other) if other != id => {
+                return Err(HumansortError::DuplicateItem(new_item_name.to_string()));
+            }
+            _ => {}
+        }
+        let old_value =
+            std::mem::replace(&mut self.items[item_idx].value, new_item_name.to_string());
+        self.ids_by_value.remove(&old_value);
+        self.ids_by_value.insert(new_item_name.to_string(), id);
         Ok(())
     }
-    pub fn remove_item(&mut self, item_to_remove: &String) -> Result<(), Box<dyn Error>> {
-        let item_idx = self.find_item_idx_by_value(item_to_remove)?;
-        self.items.remove(item_idx);
+    pub fn remove_item(&mut self, id: ItemId) -> Result<(), HumansortError> {
+        let item_idx = self.find_item_idx(id)?;
+        let removed = self.items.remove(item_idx);
+        self.positions.remove(&id);
+        self.ids_by_value.remove(&removed.value);
+        for (idx, item) in self.items.iter().enumerate().skip(item_idx) {
+            self.positions.insert(item.id, idx);
+        }
         Ok(())
     }
-    fn find_item_idx_by_value(&self, needle: &String) -> Result<usize, Box<dyn Error>> {
-        let maybe_item = self
+    /// Change an item's rating without moving it. The caller has to
+    /// reposition it afterward.
+    fn set_rating(&mut self, id: ItemId, rating: f32) {
+        if let Some(&idx) = self.positions.get(&id) {
+            self.items[idx].rating = rating;
+        }
+    }
+    /// Put an item's rating back to what it was in `old`, without moving it
+    /// or touching its counts. The caller has to reposition it afterward.
+    fn restore_rating(&mut self, old: &ItemSnapshot) {
+        if let Some(&idx) = self.positions.get(&old.id) {
+            let item = &mut self.items[idx];
+            item.rating = old.rating;
+            item.deviation = old.deviation;
+            item.volatility = old.volatility;
+        }
+    }
+    /// The timestamp for a judgment made now: the time, or if there's a seed,
+    /// how many judgments came before it.
+    fn timestamp(&self) -> u64 {
+        match self.seed {
+            Some(_) => self.history.len() as u64,
+            None => history::now(),
+        }
+    }
+    fn new_id(&mut self) -> ItemId {
+        self.next_id += 1;
+        ItemId(self.next_id)
+    }
+    fn find_item_idx(&self, id: ItemId) -> Result<usize, HumansortError> {
+        self.positions
+            .get(&id)
+            .copied()
+            .ok_or(HumansortError::ItemNotFound(id))
+    }
+    /// Sort all items descending by rating. Items with equal ratings keep
+    /// their order.
+    fn sort_items(&mut self) {
+        self.items
+            .sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap());
+        self.reindex();
+    }
+    /// Rebuild the lookup tables after `items` changed wholesale.
+    fn reindex(&mut self) {
+        self.positions = self
             .items
             .iter()
             .enumerate()
-            .find(|(_, l)| l.value == *needle);
-        if let Some(i) = maybe_item {
-            Ok(i.0)
-        } else {
-            Err(format!("Failed to find '{}'", needle).into())
+            .map(|(idx, item)| (item.id, idx))
+            .collect();
+        self.ids_by_value.clear();
+        for item in self.items.iter() {
+            // If an old file somehow has two items with the same text, text
+            // lookups find the first one.
+            self.ids_by_value
+                .entry(item.value.clone())
+                .or_insert(item.id);
+        }
+    }
+    /// Move the items whose ratings changed to where they now belong, and
+    /// update the positions of the items they passed. The rest of the list
+    /// has to be in order already.
+    fn reposition(&mut self, ids: &[ItemId]) {
+        let mut idxs: Vec<usize> = ids
+            .iter()
+            .filter_map(|id| self.positions.get(id).copied())
+            .collect();
+        if idxs.is_empty() {
+            return;
+        }
+        idxs.sort_unstable();
+        idxs.dedup();
+        let mut lo = idxs[0];
+        let mut hi = idxs[idxs.len() - 1];
+        // Take the items out from the back so the other indices stay valid,
+        // then put each one back after the items rated at least as high.
+        let moved: Vec<HumansortItem> = idxs
+            .iter()
+            .rev()
+            .map(|&idx| self.items.remove(idx))
+            .collect();
+        for item in moved {
+            let idx = self.items.partition_point(|i| i.rating >= item.rating);
+            lo = lo.min(idx);
+            hi = hi.max(idx);
+            self.items.insert(idx, item);
+        }
+        // Later insertions can push earlier ones back by one place each.
+        hi = (hi + idxs.len()).min(self.items.len() - 1);
+        for i in lo..=hi {
+            self.positions.insert(self.items[i].id, i);
         }
     }
 }
@@ -168,34 +869,42 @@ impl Default for HumansortState {
     fn default() -> Self {
         HumansortState {
             items: Vec::new(),
-            num_items: 5,
-            current_idx: 0,
+            num_items: default_num_items(),
+            current_idx: default_current_idx(),
+            model: Model::default(),
+            selection: Strategy::default(),
+            history: Vec::new(),
+            redo: Vec::new(),
+            seed: None,
+            min_appearances: default_min_appearances(),
+            question: None,
+            archived: Vec::new(),
+            session: None,
+            next_id: 0,
+            positions: HashMap::new(),
+            ids_by_value: HashMap::new(),
         }
     }
 }
 
 impl From<Vec<String>> for HumansortState {
     fn from(strings: Vec<String>) -> Self {
-        let mut state = Vec::new();
+        let mut state = HumansortState::default();
         // Ignore duplicate items.
         let mut unique = HashSet::new();
         for item in strings.iter() {
-            if !unique.contains(item) {
-                state.push(HumansortItem {
-                    value: item.to_string(),
-                    rating: 0.,
-                });
-                unique.insert(item.clone());
+            if unique.insert(item) {
+                // Duplicates were skipped, so this can't fail.
+                let _ = state.add_item(item);
             }
         }
-        HumansortState {
-            items: state,
-            num_items: 5,
-            current_idx: 0,
-        }
+        state
     }
 }
 
+/// Yields copies of the items from best to worst, consuming the state. Use
+/// [`HumansortState::ranked`] to look at the items without giving up the
+/// state.
 impl Iterator for HumansortState {
     type Item = HumansortItem;
 
@@ -212,8 +921,66 @@ impl Iterator for HumansortState {
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct HumansortItem {
+    /// Files from before items had IDs leave this out. It gets filled in when
+    /// the file is loaded.
+    #[serde(default)]
+    id: ItemId,
     value: String,
     rating: f32,
+    #[serde(default = "default_deviation")]
+    deviation: f32,
+    #[serde(default = "default_volatility")]
+    volatility: f32,
+    #[serde(default)]
+    comparisons: u32,
+    #[serde(default)]
+    appearances: u32,
+}
+
+fn default_deviation() -> f32 {
+    350.
+}
+
+fn default_volatility() -> f32 {
+    0.06
+}
+
+impl HumansortItem {
+    pub fn id(&self) -> ItemId {
+        self.id
+    }
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+    pub fn rating(&self) -> f32 {
+        self.rating
+    }
+    /// How far the rating could plausibly be from the item's true strength.
+    /// Only models that track uncertainty (see [`RatingModel::uncertainty`])
+    /// change this from its initial value.
+    pub fn deviation(&self) -> f32 {
+        self.deviation
+    }
+    pub fn volatility(&self) -> f32 {
+        self.volatility
+    }
+    /// How many pairwise comparisons this item has been part of.
+    pub fn comparisons(&self) -> u32 {
+        self.comparisons
+    }
+    /// How many judgments this item has been shown in, including ones where
+    /// the user couldn't decide.
+    pub fn appearances(&self) -> u32 {
+        self.appearances
+    }
+    fn reset_rating(&mut self) {
+        let fresh = HumansortItem::default();
+        self.rating = fresh.rating;
+        self.deviation = fresh.deviation;
+        self.volatility = fresh.volatility;
+        self.comparisons = fresh.comparisons;
+        self.appearances = fresh.appearances;
+    }
 }
 
 impl Display for HumansortItem {
@@ -225,8 +992,13 @@ impl Display for HumansortItem {
 impl Default for HumansortItem {
     fn default() -> Self {
         HumansortItem {
+            id: ItemId::default(),
             value: String::new(),
             rating: 0.,
+            deviation: default_deviation(),
+            volatility: default_volatility(),
+            comparisons: 0,
+            appearances: 0,
         }
     }
 }
@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{HumansortError, HumansortItem};
+
+/// The outcome of a single pairwise comparison. Items are referred to by their
+/// index in the slice passed to [`RatingModel::update`]. When `draw` is set,
+/// neither item won, and which one is called the winner doesn't matter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matchup {
+    pub winner: usize,
+    pub loser: usize,
+    pub draw: bool,
+}
+
+impl Matchup {
+    pub fn win(winner: usize, loser: usize) -> Self {
+        Matchup {
+            winner,
+            loser,
+            draw: false,
+        }
+    }
+    pub fn draw(a: usize, b: usize) -> Self {
+        Matchup {
+            winner: a,
+            loser: b,
+            draw: true,
+        }
+    }
+}
+
+/// A rule for turning comparison outcomes into rating changes.
+pub trait RatingModel {
+    /// Apply the outcomes of one judgment to `items`. All matchups from a
+    /// single judgment happen "at the same time", so expectations should be
+    /// computed from the ratings as they were before the call.
+    fn update(&self, items: &mut [HumansortItem], matchups: &[Matchup]);
+    /// How uncertain the model is about `item`'s rating, in rating points, if
+    /// the model keeps track of that at all.
+    fn uncertainty(&self, _item: &HumansortItem) -> Option<f32> {
+        None
+    }
+    /// The probability that `a` would be preferred over `b`.
+    fn win_probability(&self, a: &HumansortItem, b: &HumansortItem) -> f32;
+    /// How unsure the model is about `item`'s rating, from 0 (completely sure)
+    /// to 1 (knows nothing). Models that don't track uncertainty fall back on
+    /// how many comparisons the item has been part of.
+    fn relative_uncertainty(&self, item: &HumansortItem) -> f32 {
+        1. / (1. + item.comparisons as f32).sqrt()
+    }
+}
+
+/// The rating models that can be stored in a humansort file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Model {
+    Elo(Elo),
+    Glicko2(Glicko2),
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model::Elo(Elo::default())
+    }
+}
+
+impl Model {
+    /// Check that the model's parameters are positive, finite numbers, since
+    /// anything else turns ratings into NaN or infinity.
+    pub fn validate(&self) -> Result<(), HumansortError> {
+        let params = match self {
+            Model::Elo(elo) => vec![("k-factor", elo.k_factor), ("scale", elo.scale)],
+            Model::Glicko2(glicko2) => vec![("tau", glicko2.tau)],
+        };
+        for (name, value) in params {
+            if !(value.is_finite() && value > 0.) {
+                return Err(HumansortError::InvalidModel(format!(
+                    "{} must be a positive number (got {})",
+                    name, value
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RatingModel for Model {
+    fn update(&self, items: &mut [HumansortItem], matchups: &[Matchup]) {
+        match self {
+            Model::Elo(m) => m.update(items, matchups),
+            Model::Glicko2(m) => m.update(items, matchups),
+        }
+    }
+    fn uncertainty(&self, item: &HumansortItem) -> Option<f32> {
+        match self {
+            Model::Elo(m) => m.uncertainty(item),
+            Model::Glicko2(m) => m.uncertainty(item),
+        }
+    }
+    fn win_probability(&self, a: &HumansortItem, b: &HumansortItem) -> f32 {
+        match self {
+            Model::Elo(m) => m.win_probability(a, b),
+            Model::Glicko2(m) => m.win_probability(a, b),
+        }
+    }
+    fn relative_uncertainty(&self, item: &HumansortItem) -> f32 {
+        match self {
+            Model::Elo(m) => m.relative_uncertainty(item),
+            Model::Glicko2(m) => m.relative_uncertainty(item),
+        }
+    }
+}
+
+/// An Elo-style model. The winner gains, and the loser loses, `k_factor`
+/// times the winner's expected score, where the expected score is
+/// `1 / (1 + 10^((loser - winner) / scale))`. In a draw, each item moves by
+/// `k_factor` times the difference between half a point and its expected
+/// score, so the two ratings are pulled toward each other.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Elo {
+    pub k_factor: f32,
+    pub scale: f32,
+}
+
+impl Default for Elo {
+    fn default() -> Self {
+        Elo {
+            k_factor: 1.,
+            scale: 1.,
+        }
+    }
+}
+
+impl RatingModel for Elo {
+    fn update(&self, items: &mut [HumansortItem], matchups: &[Matchup]) {
+        // Accumulate the changes first so that every matchup sees the ratings
+        // from before this judgment.
+        let mut deltas = vec![0_f32; items.len()];
+        for m in matchups {
+            let winner_rating = items[m.winner].rating;
+            let loser_rating = items[m.loser].rating;
+            let expected = 1. / (1. + 10_f32.powf((loser_rating - winner_rating) / self.scale));
+            let change = if m.draw { 0.5 - expected } else { expected };
+            deltas[m.loser] -= self.k_factor * change;
+            deltas[m.winner] += self.k_factor * change;
+        }
+        for (item, delta) in items.iter_mut().zip(deltas) {
+            if delta != 0. {
+                item.rating += delta;
+            }
+        }
+    }
+    fn win_probability(&self, a: &HumansortItem, b: &HumansortItem) -> f32 {
+        1. / (1. + 10_f32.powf((b.rating - a.rating) / self.scale))
+    }
+}
+
+/// Conversion factor between the Glicko and Glicko-2 scales.
+const GLICKO2_SCALE: f64 = 173.7178;
+
+/// Glickman's Glicko-2 system. Ratings are stored on the Glicko scale but
+/// centered on zero instead of 1500, so they start out the same as Elo
+/// ratings. Each judgment is treated as its own rating period, and only the
+/// items that took part in it are updated.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Glicko2 {
+    /// The system constant, which limits how fast volatility can change.
+    pub tau: f32,
+}
+
+impl Default for Glicko2 {
+    fn default() -> Self {
+        Glicko2 { tau: 0.5 }
+    }
+}
+
+impl Glicko2 {
+    fn g(phi: f64) -> f64 {
+        1. / (1. + 3. * phi.powi(2) / std::f64::consts::PI.powi(2)).sqrt()
+    }
+    fn expected(mu: f64, mu_j: f64, phi_j: f64) -> f64 {
+        1. / (1. + (-Self::g(phi_j) * (mu - mu_j)).exp())
+    }
+    /// Find the new volatility with the Illinois algorithm, as in step 5 of
+    /// Glickman's paper.
+    fn new_volatility(&self, phi: f64, sigma: f64, v: f64, delta: f64) -> f64 {
+        const EPSILON: f64 = 0.000001;
+        let tau = self.tau as f64;
+        let a = sigma.powi(2).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            ex * (delta.powi(2) - phi.powi(2) - v - ex) / (2. * (phi.powi(2) + v + ex).powi(2))
+                - (x - a) / tau.powi(2)
+        };
+        let mut big_a = a;
+        let mut big_b = if delta.powi(2) > phi.powi(2) + v {
+            (delta.powi(2) - phi.powi(2) - v).ln()
+        } else {
+            let mut k = 1.;
+            while f(a - k * tau) < 0. {
+                k += 1.;
+            }
+            a - k * tau
+        };
+        let mut f_a = f(big_a);
+        let mut f_b = f(big_b);
+        while (big_b - big_a).abs() > EPSILON {
+            let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+            let f_c = f(big_c);
+            if f_c * f_b <= 0. {
+                big_a = big_b;
+                f_a = f_b;
+            } else {
+                f_a /= 2.;
+            }
+            big_b = big_c;
+            f_b = f_c;
+        }
+        (big_a / 2.).exp()
+    }
+}
+
+impl RatingModel for Glicko2 {
+    fn update(&self, items: &mut [HumansortItem], matchups: &[Matchup]) {
+        // Gather every item's games in this judgment as (opponent, score)
+        // pairs.
+        let mut games: Vec<Vec<(usize, f64)>> = vec![Vec::new(); items.len()];
+        for m in matchups {
+            let score = if m.draw { 0.5 } else { 1. };
+            games[m.winner].push((m.loser, score));
+            games[m.loser].push((m.winner, 1. - score));
+        }
+        // Convert everything to the Glicko-2 scale up front so that all items
+        // are rated against their opponents' old values.
+        let mu: Vec<f64> = items
+            .iter()
+            .map(|i| i.rating as f64 / GLICKO2_SCALE)
+            .collect();
+        let phi: Vec<f64> = items
+            .iter()
+            .map(|i| i.deviation as f64 / GLICKO2_SCALE)
+            .collect();
+        for (idx, item_games) in games.iter().enumerate() {
+            if item_games.is_empty() {
+                continue;
+            }
+            let mut v_inv = 0.;
+            let mut improvement = 0.;
+            for &(j, score) in item_games {
+                let g = Self::g(phi[j]);
+                let e = Self::expected(mu[idx], mu[j], phi[j]);
+                v_inv += g.powi(2) * e * (1. - e);
+                improvement += g * (score - e);
+            }
+            let v = 1. / v_inv;
+            let delta = v * improvement;
+            let sigma = self.new_volatility(phi[idx], items[idx].volatility as f64, v, delta);
+            let phi_star = (phi[idx].powi(2) + sigma.powi(2)).sqrt();
+            let new_phi = 1. / (1. / phi_star.powi(2) + 1. / v).sqrt();
+            let new_mu = mu[idx] + new_phi.powi(2) * improvement;
+            items[idx].rating = (new_mu * GLICKO2_SCALE) as f32;
+            items[idx].deviation = (new_phi * GLICKO2_SCALE) as f32;
+            items[idx].volatility = sigma as f32;
+        }
+    }
+    fn uncertainty(&self, item: &HumansortItem) -> Option<f32> {
+        Some(item.deviation)
+    }
+    fn win_probability(&self, a: &HumansortItem, b: &HumansortItem) -> f32 {
+        // Both ratings are uncertain, so combine their deviations.
+        let phi = (a.deviation.powi(2) + b.deviation.powi(2)).sqrt() as f64 / GLICKO2_SCALE;
+        Self::expected(
+            a.rating as f64 / GLICKO2_SCALE,
+            b.rating as f64 / GLICKO2_SCALE,
+            phi,
+        ) as f32
+    }
+    fn relative_uncertainty(&self, item: &HumansortItem) -> f32 {
+        (item.deviation / HumansortItem::default().deviation).min(1.)
+    }
+}
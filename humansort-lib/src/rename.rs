@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ItemId;
+
+/// A guess that an item which is missing from a merged list was renamed to a
+/// line that's new in that list, such as when a typo gets fixed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenameCandidate {
+    pub id: ItemId,
+    pub old_value: String,
+    pub new_value: String,
+    /// See [`similarity`].
+    pub similarity: f32,
+}
+
+/// How alike two pieces of text are, from 0 (nothing in common) to 1 (the
+/// same apart from case). This is one minus the number of single-character
+/// edits needed to turn one into the other, divided by the length of the
+/// longer one.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.;
+    }
+    1. - edit_distance(&a, &b) as f32 / longest as f32
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    bounded_edit_distance(a, b, usize::MAX).unwrap_or(usize::MAX)
+}
+
+/// The Levenshtein distance between `a` and `b`, or `None` as soon as it's
+/// clear that it's more than `max`.
+fn bounded_edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    // Only the previous row of the table is needed.
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        let mut lowest = row[0];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(row[j + 1] + 1);
+            lowest = lowest.min(row[j + 1]);
+        }
+        // Distances along a path through the table never go down.
+        if lowest > max {
+            return None;
+        }
+    }
+    Some(row[b.len()]).filter(|&d| d <= max)
+}
+
+/// How many times each pair of adjacent characters appears in `text`,
+/// counting the start and end as characters so that even one-character text
+/// has some.
+fn bigrams(text: &[char]) -> HashMap<(char, char), u32> {
+    let mut counts = HashMap::new();
+    let padded: Vec<char> = ['\0']
+        .iter()
+        .chain(text)
+        .chain(['\0'].iter())
+        .copied()
+        .collect();
+    for pair in padded.windows(2) {
+        *counts.entry((pair[0], pair[1])).or_default() += 1;
+    }
+    counts
+}
+
+/// How many of the lines that have the most pairs of adjacent characters in
+/// common with a missing item are compared with it in full. A line that's an
+/// edit of the item shares nearly all of them, so it's almost always among
+/// these.
+const CANDIDATES_PER_ITEM: usize = 16;
+
+/// How much work [`pair_renames`] does before it stops looking, roughly in
+/// characters compared. This keeps merging a list that was mostly replaced
+/// from taking minutes to look for renames that are unlikely to be there.
+const MAX_WORK: usize = 100_000_000;
+
+/// Pair up `missing` items with `added` lines, most similar pairs first, so
+/// that each item and each line is used at most once. Pairs less similar than
+/// `min_similarity` are left out. To keep this fast on long lists, each item
+/// is only compared in full with the [`CANDIDATES_PER_ITEM`] lines most like
+/// it, and it gives up on the remaining items after [`MAX_WORK`].
+pub(crate) fn pair_renames(
+    missing: &[(ItemId, &str)],
+    added: &[&str],
+    min_similarity: f32,
+) -> Vec<RenameCandidate> {
+    let lowered: Vec<Vec<char>> = added
+        .iter()
+        .map(|value| value.to_lowercase().chars().collect())
+        .collect();
+    // Index the added lines by the pairs of adjacent characters in them.
+    let mut index: HashMap<(char, char), Vec<(usize, u32)>> = HashMap::new();
+    for (j, line) in lowered.iter().enumerate() {
+        for (bigram, count) in bigrams(line) {
+            index.entry(bigram).or_default().push((j, count));
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let mut shared = vec![0_u32; added.len()];
+    let mut work = 0;
+    for (i, &(_, old_value)) in missing.iter().enumerate() {
+        if work > MAX_WORK {
+            break;
+        }
+        let old: Vec<char> = old_value.to_lowercase().chars().collect();
+        // Count the pairs each line has in common with the item.
+        let mut touched = Vec::new();
+        for (bigram, count) in bigrams(&old) {
+            let Some(lines) = index.get(&bigram) else {
+                continue;
+            };
+            work += lines.len();
+            for &(j, other) in lines {
+                if shared[j] == 0 {
+                    touched.push(j);
+                }
+                shared[j] += count.min(other);
+            }
+        }
+        // The length difference alone puts a ceiling on the similarity.
+        let similar_length = |j: &&usize| {
+            let (a, b) = (old.len(), lowered[**j].len());
+            a.min(b) as f32 >= min_similarity * a.max(b) as f32
+        };
+        let mut candidates: Vec<(u32, usize)> = touched
+            .iter()
+            .filter(similar_length)
+            .map(|&j| (shared[j], j))
+            .collect();
+        for &j in &touched {
+            shared[j] = 0;
+        }
+        candidates.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for &(_, j) in candidates.iter().take(CANDIDATES_PER_ITEM) {
+            let new = &lowered[j];
+            let len = old.len().max(new.len());
+            // The extra edit allows for rounding.
+            let max_edits = ((1. - min_similarity) * len as f32) as usize + 1;
+            work += old.len() * new.len();
+            let Some(distance) = bounded_edit_distance(&old, new, max_edits) else {
+                continue;
+            };
+            let similarity = match len {
+                0 => 1.,
+                _ => 1. - distance as f32 / len as f32,
+            };
+            if similarity >= min_similarity {
+                pairs.push((similarity, i, j));
+            }
+        }
+    }
+    // Ties go to the items and lines that come first.
+    pairs.sort_by(|a, b| b.0.total_cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+    let mut used_items = HashSet::new();
+    let mut used_lines = HashSet::new();
+    pairs
+        .into_iter()
+        .filter(|&(_, i, j)| {
+            !used_items.contains(&i)
+                && !used_lines.contains(&j)
+                && used_items.insert(i)
+                && used_lines.insert(j)
+        })
+        .map(|(similarity, i, j)| RenameCandidate {
+            id: missing[i].0,
+            old_value: missing[i].1.to_string(),
+            new_value: added[j].to_string(),
+            similarity,
+        })
+        .collect()
+}
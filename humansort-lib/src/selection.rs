@@ -0,0 +1,218 @@
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::{HumansortItem, RatingModel};
+
+/// A rule for choosing which items to show the user next.
+pub trait SelectionStrategy {
+    /// Choose `num_items` distinct indices into `items`, which are sorted
+    /// descending by rating and contain at least `num_items` entries.
+    fn select(
+        &self,
+        items: &[HumansortItem],
+        model: &dyn RatingModel,
+        num_items: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<usize>;
+}
+
+/// The selection strategies that a humansort state can use.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Strategy {
+    Quadratic(Quadratic),
+    ActiveLearning(ActiveLearning),
+    TopK(TopK),
+}
+
+impl Default for Strategy {
+    fn default() -> Self {
+        Strategy::Quadratic(Quadratic)
+    }
+}
+
+impl SelectionStrategy for Strategy {
+    fn select(
+        &self,
+        items: &[HumansortItem],
+        model: &dyn RatingModel,
+        num_items: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<usize> {
+        match self {
+            Strategy::Quadratic(s) => s.select(items, model, num_items, rng),
+            Strategy::ActiveLearning(s) => s.select(items, model, num_items, rng),
+            Strategy::TopK(s) => s.select(items, model, num_items, rng),
+        }
+    }
+}
+
+/// Pick items at random with a preference for higher-rated ones. (This avoids
+/// prompting the user for more information on items that they rated lower
+/// already.) Each pick is an index `floor(x^2 * len)` for a uniform `x`,
+/// redrawn if it was already picked.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Quadratic;
+
+impl SelectionStrategy for Quadratic {
+    fn select(
+        &self,
+        items: &[HumansortItem],
+        _model: &dyn RatingModel,
+        num_items: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<usize> {
+        // The chance that `floor(x^2 * len)` lands on index `i` is the
+        // difference of two square roots. Sampling without replacement by
+        // these weights gives the same distribution as redrawing duplicates,
+        // but takes the same time no matter how many items are wanted.
+        let len = items.len() as f64;
+        let weights: Vec<f64> = (0..items.len())
+            .map(|i| ((i + 1) as f64 / len).sqrt() - (i as f64 / len).sqrt())
+            .collect();
+        weighted_sample(&weights, num_items, rng)
+    }
+}
+
+/// Choose `amount` distinct indices into `weights`, where the chance of each
+/// pick is proportional to the weights of the indices not yet picked. This
+/// is Efraimidis and Spirakis's algorithm: give each index the key
+/// `u^(1 / weight)` for a uniform `u` and keep the largest keys, in order.
+/// It runs in linear time.
+fn weighted_sample(weights: &[f64], amount: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+    // Compare logarithms of the keys, which don't underflow for tiny weights.
+    let mut keys: Vec<(usize, f64)> = weights
+        .iter()
+        .map(|&w| {
+            let u: f64 = rng.gen_range(f64::MIN_POSITIVE..1.);
+            u.ln() / w
+        })
+        .enumerate()
+        .collect();
+    let by_key = |a: &(usize, f64), b: &(usize, f64)| b.1.partial_cmp(&a.1).unwrap();
+    if amount < keys.len() {
+        keys.select_nth_unstable_by(amount, by_key);
+        keys.truncate(amount);
+    }
+    keys.sort_by(by_key);
+    keys.into_iter().map(|(idx, _)| idx).collect()
+}
+
+/// Pick the set of items whose outcome should tell the model the most. A
+/// comparison is informative when its result is hard to predict and when the
+/// items in it have uncertain ratings, so the set is built greedily: start
+/// from the most uncertain item, then keep adding whichever item would make
+/// the most informative comparisons with the items already picked.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ActiveLearning;
+
+impl SelectionStrategy for ActiveLearning {
+    fn select(
+        &self,
+        items: &[HumansortItem],
+        model: &dyn RatingModel,
+        num_items: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<usize> {
+        let uncertainty: Vec<f32> = items
+            .iter()
+            .map(|i| model.relative_uncertainty(i))
+            .collect();
+        // Break near-ties at random so that a fresh list doesn't show the same
+        // items over and over.
+        let jitter: Vec<f32> = (0..items.len())
+            .map(|_| rng.gen_range(0.9_f32..1_f32))
+            .collect();
+        let information = |a: usize, b: usize| {
+            let p = model.win_probability(&items[a], &items[b]);
+            p * (1. - p) * (uncertainty[a] + uncertainty[b])
+        };
+
+        let mut chosen = Vec::with_capacity(num_items);
+        let mut is_chosen = vec![false; items.len()];
+        let mut gain = uncertainty.clone();
+        while chosen.len() < num_items {
+            let next = (0..items.len())
+                .filter(|&i| !is_chosen[i])
+                .max_by(|&a, &b| {
+                    (gain[a] * jitter[a])
+                        .partial_cmp(&(gain[b] * jitter[b]))
+                        .unwrap()
+                })
+                .unwrap();
+            // After the first pick, gains only count comparisons with the
+            // chosen items.
+            if chosen.is_empty() {
+                gain.iter_mut().for_each(|g| *g = 0.);
+            }
+            chosen.push(next);
+            is_chosen[next] = true;
+            for (i, g) in gain.iter_mut().enumerate() {
+                if !is_chosen[i] {
+                    *g += information(next, i);
+                }
+            }
+        }
+        chosen
+    }
+}
+
+/// Focus on the items that might or might not belong in the top `k`, for when
+/// only the best few items matter and the order of the rest doesn't.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TopK {
+    pub k: usize,
+}
+
+impl TopK {
+    /// How unsure the model is whether each item belongs in the top `k`,
+    /// from 0 (completely sure) to 1 (no idea). An item inside the top `k` is
+    /// measured against the best item outside it, and vice versa. `items`
+    /// must be sorted descending by rating.
+    pub fn membership_uncertainty(
+        &self,
+        items: &[HumansortItem],
+        model: &dyn RatingModel,
+    ) -> Vec<f32> {
+        items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let boundary = if idx < self.k {
+                    items.get(self.k)
+                } else {
+                    self.k.checked_sub(1).map(|b| &items[b])
+                };
+                // How clearly the item is on its side of the boundary, taking
+                // into account how little the model may know about it.
+                let margin =
+                    boundary.map_or(1., |b| (2. * model.win_probability(item, b) - 1.).abs());
+                1. - margin * (1. - model.relative_uncertainty(item))
+            })
+            .collect()
+    }
+}
+
+impl SelectionStrategy for TopK {
+    fn select(
+        &self,
+        items: &[HumansortItem],
+        model: &dyn RatingModel,
+        num_items: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<usize> {
+        let uncertainty = self.membership_uncertainty(items, model);
+        // Break near-ties at random, as in `ActiveLearning`.
+        let mut scored: Vec<(usize, f32)> = uncertainty
+            .into_iter()
+            .map(|u| u * rng.gen_range(0.9_f32..1_f32))
+            .enumerate()
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        scored
+            .into_iter()
+            .take(num_items)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+}
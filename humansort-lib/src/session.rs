@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    exact::ExactStep, Bracket, ExactSort, HumansortState, Insertion, ItemId, RoundRobin, Swiss,
+    TournamentProgress,
+};
+
+/// A structured way of sorting that asks its own questions instead of
+/// leaving them to the selection strategy. A state keeps at most one session
+/// at a time, and saves it along with everything else so that it can be
+/// picked up later. Answers are recorded with the usual judgment methods, so
+/// they update the ratings too unless the session says otherwise.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Session {
+    Exact(ExactSort),
+    Insertion(Insertion),
+    RoundRobin(RoundRobin),
+    Swiss(Swiss),
+    Bracket(Bracket),
+}
+
+impl Session {
+    /// The items to show next, or `None` once the session is over.
+    pub fn next(&self, state: &HumansortState) -> Option<Vec<ItemId>> {
+        match self {
+            Session::Exact(s) => match s.step(state) {
+                ExactStep::Compare(pair) => Some(pair.to_vec()),
+                ExactStep::Done(_) => None,
+            },
+            Session::Insertion(s) => s.next(state).map(|pair| pair.to_vec()),
+            _ => self.tournament(state)?.next.map(|pair| pair.to_vec()),
+        }
+    }
+    /// The most questions it could take to finish the session.
+    pub fn questions_left(&self, state: &HumansortState) -> usize {
+        match self {
+            Session::Exact(s) => s.questions_left(state),
+            Session::Insertion(s) => s.questions_left(state),
+            _ => self.tournament(state).map_or(0, |t| t.games_left),
+        }
+    }
+    /// Make any changes the session calls for after the latest judgment,
+    /// which is already in the history.
+    pub(crate) fn settle(&self, state: &mut HumansortState) {
+        if let Session::Insertion(s) = self {
+            s.settle(state);
+        }
+    }
+    /// Keep a tournament's starting point within the first `len` judgments,
+    /// so that after the history is undone past it, the next answers still
+    /// count toward the tournament.
+    pub(crate) fn truncate_history(&mut self, len: usize) {
+        let start = match self {
+            Session::RoundRobin(s) => &mut s.start,
+            Session::Swiss(s) => &mut s.start,
+            Session::Bracket(s) => &mut s.start,
+            _ => return,
+        };
+        *start = (*start).min(len);
+    }
+    /// How a tournament is going, if this session is one.
+    pub fn tournament(&self, state: &HumansortState) -> Option<TournamentProgress> {
+        match self {
+            Session::RoundRobin(s) => Some(s.progress(state)),
+            Session::Swiss(s) => Some(s.progress(state)),
+            Session::Bracket(s) => Some(s.progress(state)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    default_min_appearances, default_num_items, HumansortError, HumansortItem, HumansortState,
+    ItemId, Judgment, Model, Session, Strategy,
+};
+
+/// The version of the file format that this version of humansort writes.
+/// Whenever the format changes, bump this and add a step to `MIGRATIONS` that
+/// upgrades files from the previous version.
+pub const FORMAT_VERSION: u64 = 5;
+
+/// `MIGRATIONS[n]` upgrades a file from version `n` to version `n + 1`.
+/// Files from before the format had a version are version 0.
+const MIGRATIONS: [fn(Value) -> Result<Value, HumansortError>; FORMAT_VERSION as usize] =
+    [v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5];
+
+/// A humansort state as the current version of the format lays it out.
+#[derive(Deserialize)]
+struct StoredState {
+    items: Vec<HumansortItem>,
+    #[serde(default)]
+    settings: Settings,
+    #[serde(default)]
+    history: Vec<Judgment>,
+    #[serde(default)]
+    redo: Vec<Judgment>,
+    #[serde(default)]
+    archived: Vec<HumansortItem>,
+    #[serde(default)]
+    session: Option<Session>,
+    next_id: u64,
+}
+
+/// The same layout as `StoredState`, but borrowed so that saving doesn't have
+/// to copy anything.
+#[derive(Serialize)]
+struct SavedState<'a> {
+    version: u64,
+    items: &'a [HumansortItem],
+    settings: Settings,
+    history: &'a [Judgment],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    redo: &'a [Judgment],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    archived: &'a [HumansortItem],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<&'a Session>,
+    next_id: u64,
+}
+
+/// How the user wants this file to be sorted, which every front end should
+/// honor.
+#[derive(Serialize, Deserialize)]
+struct Settings {
+    #[serde(default = "default_num_items")]
+    num_items: usize,
+    #[serde(default)]
+    selection: Strategy,
+    #[serde(default)]
+    model: Model,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    question: Option<String>,
+    #[serde(default = "default_min_appearances")]
+    min_appearances: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            num_items: default_num_items(),
+            selection: Strategy::default(),
+            model: Model::default(),
+            question: None,
+            min_appearances: default_min_appearances(),
+            seed: None,
+        }
+    }
+}
+
+impl Serialize for HumansortState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SavedState {
+            version: FORMAT_VERSION,
+            items: &self.items,
+            settings: Settings {
+                num_items: self.num_items,
+                selection: self.selection.clone(),
+                model: self.model.clone(),
+                question: self.question.clone(),
+                min_appearances: self.min_appearances,
+                seed: self.seed,
+            },
+            history: &self.history,
+            redo: &self.redo,
+            archived: &self.archived,
+            session: self.session.as_ref(),
+            next_id: self.next_id,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for HumansortState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        load(Value::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// Read a stored state of any version up to `FORMAT_VERSION`, upgrading it
+/// one version at a time. The version is checked before anything else, so
+/// that a file from a newer version gets a clear error instead of a confusing
+/// one about some field.
+fn load(mut value: Value) -> Result<HumansortState, HumansortError> {
+    let version = match value.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| HumansortError::InvalidFile(format!("bad version {}", v)))?,
+    };
+    if version > FORMAT_VERSION {
+        return Err(HumansortError::NewerFormat {
+            version,
+            supported: FORMAT_VERSION,
+        });
+    }
+    for migrate in MIGRATIONS[version as usize..].iter() {
+        value = migrate(value)?;
+    }
+    let stored: StoredState = serde_json::from_value(value).map_err(invalid_file)?;
+    let mut state = HumansortState {
+        items: stored.items,
+        model: stored.settings.model,
+        selection: stored.settings.selection,
+        question: stored.settings.question,
+        history: stored.history,
+        redo: stored.redo,
+        archived: stored.archived,
+        session: stored.session,
+        seed: stored.settings.seed,
+        min_appearances: stored.settings.min_appearances,
+        next_id: stored.next_id,
+        ..Default::default()
+    };
+    state.set_num_items(stored.settings.num_items)?;
+    // Older versions didn't always keep items in order.
+    state.sort_items();
+    Ok(state)
+}
+
+fn invalid_file(e: serde_json::Error) -> HumansortError {
+    HumansortError::InvalidFile(e.to_string())
+}
+
+/// How a version 0 judgment refers to an item. Files from before items had
+/// IDs use the item's text instead.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ItemRef {
+    Id(ItemId),
+    Value(String),
+}
+
+/// Version 0 covers every file from before the format had a version, from
+/// bare lists of items up to files with IDs, so almost everything is
+/// optional.
+#[derive(Deserialize)]
+struct V0State {
+    items: Vec<HumansortItem>,
+    #[serde(default)]
+    model: Model,
+    #[serde(default)]
+    history: Vec<Judgment<ItemRef>>,
+    #[serde(default)]
+    redo: Vec<Judgment<ItemRef>>,
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default = "default_min_appearances")]
+    min_appearances: u32,
+    #[serde(default)]
+    next_id: u64,
+}
+
+/// Give every item an ID, and refer to items by ID throughout the history.
+fn v0_to_v1(mut value: Value) -> Result<Value, HumansortError> {
+    // Judgments kept whole copies of the items they changed. Copies without
+    // an ID refer to their item by its text, like the rest of the judgment.
+    for list in ["history", "redo"] {
+        let judgments = value.get_mut(list).and_then(Value::as_array_mut);
+        for judgment in judgments.into_iter().flatten() {
+            let before = judgment.get_mut("before").and_then(Value::as_array_mut);
+            for old in before.into_iter().flatten() {
+                let has_id = old.get("id").and_then(Value::as_u64).unwrap_or(0) != 0;
+                if let (false, Some(text)) = (has_id, old.get("value").cloned()) {
+                    old["id"] = text;
+                }
+            }
+        }
+    }
+    let stored: V0State = serde_json::from_value(value).map_err(invalid_file)?;
+
+    // New IDs have to be higher than any ID in use, including ones in the
+    // history that belong to items which have since been removed.
+    let mut highest = stored.next_id;
+    for item in stored.items.iter() {
+        highest = highest.max(item.id.0);
+    }
+    for judgment in stored.history.iter().chain(stored.redo.iter()) {
+        for item in judgment.shown.iter() {
+            if let ItemRef::Id(id) = item {
+                highest = highest.max(id.0);
+            }
+        }
+        for old in judgment.before.iter() {
+            if let ItemRef::Id(id) = old.id {
+                highest = highest.max(id.0);
+            }
+        }
+    }
+
+    // Give IDs to items that don't have one, and then use them to look up
+    // items that the history refers to by text. Text that doesn't match any
+    // current item belonged to an item that was removed, which gets an ID of
+    // its own.
+    let mut assigner = Assigner {
+        by_value: HashMap::new(),
+        next_id: highest,
+    };
+    let mut items = stored.items;
+    for item in items.iter_mut() {
+        if item.id == ItemId::default() {
+            // Older versions could end up with two items with the same text.
+            // They still need IDs of their own.
+            item.id = match assigner.by_value.contains_key(&item.value) {
+                true => assigner.fresh_id(),
+                false => assigner.id_for(&item.value),
+            };
+        } else {
+            assigner.by_value.insert(item.value.clone(), item.id);
+        }
+    }
+    let history: Vec<Judgment> = stored
+        .history
+        .into_iter()
+        .map(|j| assigner.resolve_judgment(j))
+        .collect();
+    let redo: Vec<Judgment> = stored
+        .redo
+        .into_iter()
+        .map(|j| assigner.resolve_judgment(j))
+        .collect();
+
+    // Appearances weren't always counted. Count them from the history, or
+    // from comparisons where the history doesn't go back far enough, and
+    // treat items that were rated before either was kept as already shown
+    // enough, so that a list sorted long ago isn't shown all over again.
+    for item in items.iter_mut().filter(|item| item.appearances == 0) {
+        let shown = history
+            .iter()
+            .filter(|j| j.shown.contains(&item.id))
+            .count() as u32;
+        item.appearances = shown.max(item.comparisons);
+        if item.appearances == 0 && item.rating != HumansortItem::default().rating {
+            item.appearances = stored.min_appearances;
+        }
+    }
+
+    Ok(json!({
+        "version": 1,
+        "items": items,
+        "model": stored.model,
+        "history": history,
+        "redo": redo,
+        "seed": stored.seed,
+        "min_appearances": stored.min_appearances,
+        "next_id": assigner.next_id,
+    }))
+}
+
+/// Gather the settings that used to sit at the top level into a section of
+/// their own, next to the ones that weren't saved before.
+fn v1_to_v2(mut value: Value) -> Result<Value, HumansortError> {
+    let state = value
+        .as_object_mut()
+        .ok_or_else(|| HumansortError::InvalidFile("expected an object".to_string()))?;
+    let mut settings = Map::new();
+    for key in ["model", "seed", "min_appearances"] {
+        if let Some(setting) = state.remove(key) {
+            settings.insert(key.to_string(), setting);
+        }
+    }
+    state.insert("settings".to_string(), Value::Object(settings));
+    state.insert("version".to_string(), json!(2));
+    Ok(value)
+}
+
+/// Version 3 can keep archived items, which older files don't have.
+fn v2_to_v3(mut value: Value) -> Result<Value, HumansortError> {
+    value
+        .as_object_mut()
+        .ok_or_else(|| HumansortError::InvalidFile("expected an object".to_string()))?
+        .insert("version".to_string(), json!(3));
+    Ok(value)
+}
+
+/// Version 4 can keep a structured session in progress, which older files
+/// don't have.
+fn v3_to_v4(mut value: Value) -> Result<Value, HumansortError> {
+    value
+        .as_object_mut()
+        .ok_or_else(|| HumansortError::InvalidFile("expected an object".to_string()))?
+        .insert("version".to_string(), json!(4));
+    Ok(value)
+}
+
+/// Version 5 keeps only the ratings and counts that a judgment changed, not
+/// whole copies of the items. The rest of each copy is ignored when loading,
+/// so there's nothing to change.
+fn v4_to_v5(mut value: Value) -> Result<Value, HumansortError> {
+    value
+        .as_object_mut()
+        .ok_or_else(|| HumansortError::InvalidFile("expected an object".to_string()))?
+        .insert("version".to_string(), json!(5));
+    Ok(value)
+}
+
+/// Hands out IDs to items that don't have one yet, giving the same ID to
+/// every mention of the same text.
+struct Assigner {
+    by_value: HashMap<String, ItemId>,
+    next_id: u64,
+}
+
+impl Assigner {
+    fn id_for(&mut self, value: &str) -> ItemId {
+        if let Some(&id) = self.by_value.get(value) {
+            return id;
+        }
+        let id = self.fresh_id();
+        self.by_value.insert(value.to_string(), id);
+        id
+    }
+    fn fresh_id(&mut self) -> ItemId {
+        self.next_id += 1;
+        ItemId(self.next_id)
+    }
+    fn resolve(&mut self, item: ItemRef) -> ItemId {
+        match item {
+            ItemRef::Id(id) => id,
+            ItemRef::Value(value) => self.id_for(&value),
+        }
+    }
+    fn resolve_judgment(&mut self, judgment: Judgment<ItemRef>) -> Judgment {
+        judgment.map_items(|item| self.resolve(item))
+    }
+}
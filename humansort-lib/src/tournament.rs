@@ -0,0 +1,441 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{HumansortState, ItemId, Judgment};
+
+/// Where an item finished in a tournament.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub id: ItemId,
+    /// One for every win and half for every draw. A bye counts as a win in a
+    /// Swiss tournament.
+    pub points: f32,
+}
+
+/// How far a tournament has got with the games played so far.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentProgress {
+    /// The next game to play, or `None` once the tournament is over.
+    pub next: Option<[ItemId; 2]>,
+    /// Every entrant, best first. Until the tournament is over, this is only
+    /// how things stand.
+    pub standings: Vec<Standing>,
+    /// The most games it could take to finish the tournament.
+    pub games_left: usize,
+}
+
+/// Every entrant plays every other entrant once. Games are scheduled with the
+/// circle method, so that nobody plays twice in a row if it can be helped.
+///
+/// Like the other tournaments, this only counts the judgments made since it
+/// started, and picks them up in the order its games were asked.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RoundRobin {
+    /// The entrants, best seed first.
+    pub items: Vec<ItemId>,
+    /// How long the history was when the tournament started.
+    pub start: usize,
+}
+
+impl RoundRobin {
+    pub fn new(state: &HumansortState, items: &[ItemId]) -> Self {
+        RoundRobin {
+            items: items.to_vec(),
+            start: state.history.len(),
+        }
+    }
+    pub fn progress(&self, state: &HumansortState) -> TournamentProgress {
+        let items = present(state, &self.items);
+        let mut results = Results::since(state, self.start);
+        let mut tally = Tally::default();
+        let schedule = circle_schedule(&items);
+        for (idx, &[a, b]) in schedule.iter().enumerate() {
+            match results.next(a, b, true) {
+                Some(game) => tally.record(a, b, game),
+                None => {
+                    return TournamentProgress {
+                        next: Some([a, b]),
+                        standings: tally.standings(&items),
+                        games_left: schedule.len() - idx,
+                    }
+                }
+            }
+        }
+        tally.finish(&items)
+    }
+}
+
+/// Every pairing of `items`, arranged in rounds by the circle method: one
+/// entrant stays put while the rest rotate around it.
+fn circle_schedule(items: &[ItemId]) -> Vec<[ItemId; 2]> {
+    let mut circle: Vec<Option<ItemId>> = items.iter().copied().map(Some).collect();
+    if circle.len() % 2 == 1 {
+        // Whoever is paired with the gap sits the round out.
+        circle.push(None);
+    }
+    let n = circle.len();
+    let mut schedule = Vec::new();
+    for _ in 1..n {
+        for i in 0..n / 2 {
+            if let (Some(a), Some(b)) = (circle[i], circle[n - 1 - i]) {
+                schedule.push([a, b]);
+            }
+        }
+        circle[1..].rotate_right(1);
+    }
+    schedule
+}
+
+/// A fixed number of rounds in which entrants with similar scores play each
+/// other, which ranks a field well with far fewer games than a round robin.
+/// Each round, entrants are ordered by points (then seed) and paired from the
+/// top down, avoiding rematches where possible. With an odd number of
+/// entrants, the lowest one that hasn't had a bye yet sits the round out and
+/// gets a point for it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Swiss {
+    /// The entrants, best seed first.
+    pub items: Vec<ItemId>,
+    pub rounds: usize,
+    /// How long the history was when the tournament started.
+    pub start: usize,
+}
+
+impl Swiss {
+    /// A Swiss tournament with enough rounds, `ceil(log2(n))`, to pick out a
+    /// clear winner.
+    pub fn new(state: &HumansortState, items: &[ItemId]) -> Self {
+        Swiss {
+            items: items.to_vec(),
+            rounds: (usize::BITS - items.len().saturating_sub(1).leading_zeros()) as usize,
+            start: state.history.len(),
+        }
+    }
+    pub fn progress(&self, state: &HumansortState) -> TournamentProgress {
+        let items = present(state, &self.items);
+        let mut results = Results::since(state, self.start);
+        let mut tally = Tally::default();
+        let mut had_bye = HashSet::new();
+        for round in 0..self.rounds {
+            let pairs = self.pair_round(&items, &mut tally, &mut had_bye);
+            for (idx, &[a, b]) in pairs.iter().enumerate() {
+                match results.next(a, b, true) {
+                    Some(game) => tally.record(a, b, game),
+                    None => {
+                        let later = (self.rounds - round - 1) * (items.len() / 2);
+                        return TournamentProgress {
+                            next: Some([a, b]),
+                            standings: tally.standings(&items),
+                            games_left: pairs.len() - idx + later,
+                        };
+                    }
+                }
+            }
+        }
+        tally.finish(&items)
+    }
+    fn pair_round(
+        &self,
+        items: &[ItemId],
+        tally: &mut Tally,
+        had_bye: &mut HashSet<ItemId>,
+    ) -> Vec<[ItemId; 2]> {
+        let mut order: Vec<ItemId> = tally.standings(items).into_iter().map(|s| s.id).collect();
+        if order.len() % 2 == 1 {
+            let bye = order
+                .iter()
+                .rposition(|id| !had_bye.contains(id))
+                .unwrap_or(order.len() - 1);
+            let bye = order.remove(bye);
+            had_bye.insert(bye);
+            *tally.points.entry(bye).or_default() += 1.;
+        }
+        let mut pairs = Vec::new();
+        while !order.is_empty() {
+            let a = order.remove(0);
+            // Prefer the closest entrant that hasn't played this one yet.
+            let b = order
+                .iter()
+                .position(|&b| !tally.played.contains(&(a, b)))
+                .unwrap_or(0);
+            pairs.push([a, order.remove(b)]);
+        }
+        pairs
+    }
+}
+
+/// A knockout bracket, seeded so that the best entrants meet as late as
+/// possible. When the number of entrants isn't a power of two, the top seeds
+/// get byes in the first round. In double elimination, an entrant is only out
+/// after losing twice: losers drop into a second bracket, whose survivors
+/// face the newcomers each round, and the last entrant from it meets the
+/// unbeaten one in the final (twice, if the unbeaten one loses the first
+/// time). Games can't end in a tie, so a tie is asked again.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Bracket {
+    /// The entrants, best seed first.
+    pub items: Vec<ItemId>,
+    pub double_elimination: bool,
+    /// How long the history was when the tournament started.
+    pub start: usize,
+}
+
+impl Bracket {
+    pub fn new(state: &HumansortState, items: &[ItemId], double_elimination: bool) -> Self {
+        Bracket {
+            items: items.to_vec(),
+            double_elimination,
+            start: state.history.len(),
+        }
+    }
+    pub fn progress(&self, state: &HumansortState) -> TournamentProgress {
+        let items = present(state, &self.items);
+        let mut results = Results::since(state, self.start);
+        let mut tally = Tally::default();
+        let lives = if self.double_elimination { 2 } else { 1 };
+        let mut losses: HashMap<ItemId, usize> = HashMap::new();
+        // Entrants who are out, in the order they went out.
+        let mut out: Vec<ItemId> = Vec::new();
+
+        // Unbeaten entrants, in bracket order, and entrants with one loss.
+        let mut upper: Vec<Option<ItemId>> = seeded_slots(items.len())
+            .into_iter()
+            .map(|seed| items.get(seed).copied())
+            .collect();
+        let mut lower: Vec<ItemId> = Vec::new();
+        loop {
+            let alive: Vec<ItemId> = upper
+                .iter()
+                .flatten()
+                .chain(lower.iter())
+                .copied()
+                .collect();
+            if alive.len() <= 1 {
+                break;
+            }
+            // Play this round: unbeaten entrants in pairs of bracket slots,
+            // then entrants with one loss in adjacent pairs. In the final, the
+            // last of each meet.
+            let mut games: Vec<[ItemId; 2]> = Vec::new();
+            let mut slots: Vec<Slot> = Vec::new();
+            let mut survivors: Vec<ItemId> = Vec::new();
+            let is_final = matches!((&upper[..], &lower[..]), ([Some(_)], [_]));
+            if is_final {
+                games.push([upper[0].unwrap(), lower[0]]);
+            } else {
+                for pair in upper.chunks(2) {
+                    match pair {
+                        [Some(a), Some(b)] => {
+                            slots.push(Slot::Game(games.len()));
+                            games.push([*a, *b]);
+                        }
+                        _ => slots.push(Slot::Bye(pair.iter().flatten().next().copied())),
+                    }
+                }
+            }
+            let upper_games = games.len();
+            if !is_final {
+                for pair in lower.chunks(2) {
+                    match pair {
+                        [a, b] => games.push([*a, *b]),
+                        _ => survivors.extend_from_slice(pair),
+                    }
+                }
+            }
+
+            let mut winners = Vec::with_capacity(games.len());
+            let mut dropped = Vec::new();
+            for &[a, b] in games.iter() {
+                let Some(Game::Won(winner)) = results.next(a, b, false) else {
+                    let left: usize = alive
+                        .iter()
+                        .map(|id| lives - losses.get(id).copied().unwrap_or_default())
+                        .sum();
+                    return TournamentProgress {
+                        next: Some([a, b]),
+                        standings: placings(&items, &out, &tally),
+                        games_left: left - 1,
+                    };
+                };
+                let loser = if winner == a { b } else { a };
+                tally.record(a, b, Game::Won(winner));
+                let lost = losses.entry(loser).or_default();
+                *lost += 1;
+                if *lost >= lives {
+                    out.push(loser);
+                } else {
+                    dropped.push(loser);
+                }
+                winners.push(winner);
+            }
+
+            if is_final {
+                // If the unbeaten entrant lost, both have lost once now, and
+                // they play again.
+                upper = match losses.get(&games[0][0]) {
+                    None => vec![Some(winners[0])],
+                    Some(_) => Vec::new(),
+                };
+                lower = match upper.is_empty() {
+                    true => vec![winners[0], games[0][0]],
+                    false => Vec::new(),
+                };
+                continue;
+            }
+            // Winners in the upper bracket keep their places, so that the
+            // bracket plays out as seeded.
+            upper = slots
+                .into_iter()
+                .map(|slot| match slot {
+                    Slot::Bye(id) => id,
+                    Slot::Game(idx) => Some(winners[idx]),
+                })
+                .collect();
+            survivors.extend_from_slice(&winners[upper_games..]);
+            lower = interleave(survivors, dropped);
+        }
+        TournamentProgress {
+            next: None,
+            standings: placings(&items, &out, &tally),
+            games_left: 0,
+        }
+    }
+}
+
+/// What a pair of slots in the upper bracket sends on to the next round.
+enum Slot {
+    /// Someone who goes through without playing, if anyone.
+    Bye(Option<ItemId>),
+    /// The winner of this game in the round.
+    Game(usize),
+}
+
+/// Positions in a bracket of the next power of two at or above `n`, listed
+/// as seeds (0 for the top seed) in the order they sit in the bracket. Seeds
+/// of `n` and above are byes.
+fn seeded_slots(n: usize) -> Vec<usize> {
+    let mut slots = vec![0];
+    while slots.len() < n {
+        let size = slots.len() * 2;
+        slots = slots
+            .into_iter()
+            .flat_map(|seed| [seed, size - 1 - seed])
+            .collect();
+    }
+    slots
+}
+
+/// Alternate between two lists, starting with the first, until both run out.
+fn interleave(a: Vec<ItemId>, b: Vec<ItemId>) -> Vec<ItemId> {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut a, mut b) = (a.into_iter(), b.into_iter());
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return merged,
+            (x, y) => merged.extend(x.into_iter().chain(y)),
+        }
+    }
+}
+
+/// Everyone still in, in seed order, followed by everyone who's out, last
+/// out first.
+fn placings(items: &[ItemId], out: &[ItemId], tally: &Tally) -> Vec<Standing> {
+    items
+        .iter()
+        .filter(|id| !out.contains(id))
+        .chain(out.iter().rev())
+        .map(|&id| Standing {
+            id,
+            points: tally.points.get(&id).copied().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Entrants that are still in the list, in the same order.
+fn present(state: &HumansortState, items: &[ItemId]) -> Vec<ItemId> {
+    items
+        .iter()
+        .copied()
+        .filter(|&id| state.item(id).is_some())
+        .collect()
+}
+
+/// The result of one game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Game {
+    Won(ItemId),
+    Drawn,
+}
+
+/// The judgments made since a tournament started, matched up with its games
+/// in the order they were asked.
+struct Results<'a> {
+    judgments: std::slice::Iter<'a, Judgment>,
+}
+
+impl<'a> Results<'a> {
+    fn since(state: &'a HumansortState, start: usize) -> Self {
+        Results {
+            judgments: state.history.get(start..).unwrap_or_default().iter(),
+        }
+    }
+    /// The result of the next game, between `a` and `b`, or `None` if it
+    /// hasn't been played yet. Judgments about anything else are passed over,
+    /// and so are skips, and ties unless `draws` is set.
+    fn next(&mut self, a: ItemId, b: ItemId, draws: bool) -> Option<Game> {
+        for judgment in self.judgments.by_ref() {
+            let shown = &judgment.shown;
+            if shown.len() != 2 || !shown.contains(&a) || !shown.contains(&b) {
+                continue;
+            }
+            match judgment.pairs().unwrap_or_default().first() {
+                Some(m) if !m.draw => return Some(Game::Won(shown[m.winner])),
+                Some(_) if draws => return Some(Game::Drawn),
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Points and pairings so far.
+#[derive(Default)]
+struct Tally {
+    points: HashMap<ItemId, f32>,
+    /// Every pair that has played, both ways round.
+    played: HashSet<(ItemId, ItemId)>,
+}
+
+impl Tally {
+    fn record(&mut self, a: ItemId, b: ItemId, game: Game) {
+        match game {
+            Game::Won(winner) => *self.points.entry(winner).or_default() += 1.,
+            Game::Drawn => {
+                *self.points.entry(a).or_default() += 0.5;
+                *self.points.entry(b).or_default() += 0.5;
+            }
+        }
+        self.played.insert((a, b));
+        self.played.insert((b, a));
+    }
+    /// `items` by points, keeping their order when points are equal.
+    fn standings(&self, items: &[ItemId]) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = items
+            .iter()
+            .map(|&id| Standing {
+                id,
+                points: self.points.get(&id).copied().unwrap_or_default(),
+            })
+            .collect();
+        standings.sort_by(|a, b| b.points.partial_cmp(&a.points).unwrap());
+        standings
+    }
+    fn finish(&self, items: &[ItemId]) -> TournamentProgress {
+        TournamentProgress {
+            next: None,
+            standings: self.standings(items),
+            games_left: 0,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::strings;
+use humansort_lib::{BradleyTerry, HumansortError, HumansortState};
+
+#[test]
+fn separate_groups_share_a_scale() {
+    // "a" beats "b" and "x" beats "y" the same number of times, but the two
+    // pairs are never compared with each other.
+    let mut state = HumansortState::from(strings(&["a", "b", "x", "y"]));
+    let [a, b, x, y] = ["a", "b", "x", "y"].map(|v| state.id_of(v).unwrap());
+    for _ in 0..3 {
+        state.judge(&[a, b], a).unwrap();
+        state.judge(&[x, y], x).unwrap();
+    }
+    let scores = BradleyTerry::default().fit(&state).unwrap();
+    let score = |id| scores.iter().find(|s| s.0 == id).unwrap().1;
+    assert!(scores.iter().all(|s| s.1.is_finite()));
+    assert!((score(a) - score(x)).abs() < 1e-6);
+    assert!((score(b) - score(y)).abs() < 1e-6);
+    assert!(score(a) > 0. && score(b) < 0.);
+}
+
+#[test]
+fn items_that_never_lost_get_finite_scores() {
+    let mut state = HumansortState::from(strings(&["a", "b", "c", "unseen"]));
+    let [a, b, c, unseen] = ["a", "b", "c", "unseen"].map(|v| state.id_of(v).unwrap());
+    for _ in 0..5 {
+        state.judge(&[a, b, c], a).unwrap();
+        state.judge(&[b, c], b).unwrap();
+    }
+    let scores = BradleyTerry::default().fit(&state).unwrap();
+    assert!(scores.iter().all(|s| s.1.is_finite()));
+    let order: Vec<_> = scores
+        .iter()
+        .map(|s| s.0)
+        .filter(|&id| id != unseen)
+        .collect();
+    assert_eq!(order, [a, b, c]);
+    // An item with no games stays level with the imaginary opponent.
+    let unseen_score = scores.iter().find(|s| s.0 == unseen).unwrap().1;
+    assert!(unseen_score.abs() < 1e-6);
+}
+
+#[test]
+fn the_prior_has_to_be_positive() {
+    let mut state = HumansortState::from(strings(&["a", "b"]));
+    let [a, b] = ["a", "b"].map(|v| state.id_of(v).unwrap());
+    state.judge(&[a, b], a).unwrap();
+    for prior in [0., -1., f64::NAN] {
+        let fit = BradleyTerry {
+            prior,
+            ..Default::default()
+        }
+        .fit(&state);
+        assert!(matches!(fit, Err(HumansortError::InvalidModel(_))));
+    }
+}
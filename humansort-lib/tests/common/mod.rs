@@ -0,0 +1,39 @@
+//! Helpers shared by the integration tests. Each test file only uses some of
+//! them.
+#![allow(dead_code)]
+
+use humansort_lib::{HumansortState, ItemId};
+use rand::RngCore;
+
+pub fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+/// The item in `shown` whose text sorts first, which is what a user would
+/// pick if the items' text were their true order.
+pub fn first_by_text(state: &HumansortState, shown: &[ItemId]) -> ItemId {
+    *shown
+        .iter()
+        .min_by_key(|&&id| state.item(id).unwrap().value().to_string())
+        .unwrap()
+}
+
+/// Answer the next prompt, chosen with `rng`, as if the items' text were
+/// their true order.
+pub fn answer_prompt(state: &mut HumansortState, rng: &mut dyn RngCore) {
+    let shown = state.next_with_rng(rng).unwrap();
+    let winner = first_by_text(state, &shown);
+    state.judge(&shown, winner).unwrap();
+}
+
+/// Answer every question in the session in progress as if the items' text
+/// were their true order, and count the questions.
+pub fn answer_all(state: &mut HumansortState) -> usize {
+    let mut questions = 0;
+    while let Some(shown) = state.session_prompt() {
+        let winner = first_by_text(state, &shown);
+        state.judge(&shown, winner).unwrap();
+        questions += 1;
+    }
+    questions
+}
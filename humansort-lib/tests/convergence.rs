@@ -0,0 +1,20 @@
+use humansort_lib::HumansortState;
+
+#[test]
+fn ratings_from_before_the_history_count_as_the_starting_point() {
+    // A file from before history was kept, already sorted, with names that
+    // sort the other way round.
+    let items: Vec<String> = (0..20)
+        .map(|i| format!(r#"{{ "value": "item {:02}", "rating": {} }}"#, i, i))
+        .collect();
+    let mut state: HumansortState =
+        serde_json::from_str(&format!(r#"{{ "items": [{}] }}"#, items.join(", "))).unwrap();
+    let best = state.id_of("item 19").unwrap();
+    let worst = state.id_of("item 00").unwrap();
+    // Judgments that agree with the ranking don't move anything, so it's as
+    // settled as it can be from the start.
+    for _ in 0..5 {
+        state.judge(&[best, worst], best).unwrap();
+        assert_eq!(state.convergence(), 1.);
+    }
+}
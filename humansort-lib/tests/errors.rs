@@ -0,0 +1,53 @@
+use humansort_lib::{HumansortError, HumansortState};
+
+fn state() -> HumansortState {
+    HumansortState::from(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+}
+
+#[test]
+fn errors_say_what_went_wrong() {
+    let mut state = state();
+    let a = state.id_of("a").unwrap();
+    let b = state.id_of("b").unwrap();
+    let c = state.id_of("c").unwrap();
+
+    assert_eq!(
+        state.add_item("a"),
+        Err(HumansortError::DuplicateItem("a".to_string()))
+    );
+    assert_eq!(
+        state.id_of("z"),
+        Err(HumansortError::ValueNotFound("z".to_string()))
+    );
+    assert_eq!(
+        state.set_num_items(1),
+        Err(HumansortError::InvalidNumItems(1))
+    );
+    // There are only three items, but prompts show five by default.
+    assert_eq!(
+        state.next(),
+        Err(HumansortError::NotEnoughItems {
+            needed: 5,
+            found: 3
+        })
+    );
+    assert!(matches!(
+        state.judge(&[a, b], c),
+        Err(HumansortError::InvalidJudgment(_))
+    ));
+    assert_eq!(state.undo().unwrap_err(), HumansortError::NothingToUndo);
+    assert_eq!(state.redo().unwrap_err(), HumansortError::NothingToRedo);
+
+    state.remove_item(c).unwrap();
+    assert_eq!(state.remove_item(c), Err(HumansortError::ItemNotFound(c)));
+}
+
+#[test]
+fn failed_judgments_change_nothing() {
+    let mut state = state();
+    let a = state.id_of("a").unwrap();
+    let before = state.clone();
+    assert!(state.judge(&[a, a], a).is_err());
+    assert!(state.judge(&[a], a).is_err());
+    assert!(state == before);
+}
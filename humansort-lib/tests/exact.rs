@@ -0,0 +1,76 @@
+mod common;
+
+use common::answer_all;
+use humansort_lib::{ExactSort, ExactStep, HumansortState, ItemId, Session};
+
+/// Items in a shuffled order, along with the order the user would put them
+/// in.
+fn shuffled(n: usize) -> (HumansortState, Vec<String>) {
+    let values: Vec<String> = (0..n).map(|i| format!("item {:02}", i)).collect();
+    let shuffled: Vec<String> = (0..n).map(|i| values[(i * 7 + 3) % n].clone()).collect();
+    (HumansortState::from(shuffled), values)
+}
+
+fn values(state: &HumansortState, order: &[ItemId]) -> Vec<String> {
+    order
+        .iter()
+        .map(|&id| state.item(id).unwrap().value().to_string())
+        .collect()
+}
+
+#[test]
+fn exact_sort_finds_the_true_order() {
+    let (mut state, expected) = shuffled(20);
+    state.start_session(Session::Exact(ExactSort));
+    let bound = ExactSort.questions_left(&state);
+    let questions = answer_all(&mut state);
+    assert!(questions <= bound);
+    match ExactSort.step(&state) {
+        ExactStep::Done(order) => assert_eq!(values(&state, &order), expected),
+        step => panic!("expected the sort to be done, got {:?}", step),
+    }
+    assert_eq!(ExactSort.questions_left(&state), 0);
+}
+
+#[test]
+fn exact_sort_resumes_from_a_saved_file() {
+    let (mut state, expected) = shuffled(8);
+    state.start_session(Session::Exact(ExactSort));
+    for _ in 0..5 {
+        let shown = state.session_prompt().unwrap();
+        state.judge(&shown, shown[0]).unwrap();
+    }
+    // Take back the last answer, then give the right ones from here on.
+    let shown = state.history().last().unwrap().shown.clone();
+    state.undo().unwrap();
+    assert_eq!(state.session_prompt(), Some(shown));
+
+    let saved = serde_json::to_string(&state).unwrap();
+    let mut state: HumansortState = serde_json::from_str(&saved).unwrap();
+    assert_eq!(state.session(), Some(&Session::Exact(ExactSort)));
+    answer_all(&mut state);
+    match ExactSort.step(&state) {
+        // The first four answers were arbitrary, so only check that every
+        // item is there.
+        ExactStep::Done(order) => {
+            let mut found = values(&state, &order);
+            found.sort();
+            assert_eq!(found, expected);
+        }
+        step => panic!("expected the sort to be done, got {:?}", step),
+    }
+}
+
+#[test]
+fn earlier_judgments_save_questions() {
+    let (mut state, _) = shuffled(6);
+    let fresh = ExactSort.questions_left(&state);
+    // Rank everything at once, which answers every question there is.
+    let mut order: Vec<ItemId> = state.ranked().map(|i| i.id).collect();
+    order.sort_by_key(|&id| state.item(id).unwrap().value().to_string());
+    let shown = order.clone();
+    state.set_num_items(6).unwrap();
+    state.rank(&shown, &order).unwrap();
+    assert!(fresh > 0);
+    assert_eq!(ExactSort.step(&state), ExactStep::Done(order));
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::answer_prompt;
+use humansort_lib::{ActiveLearning, HumansortState, Strategy};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn values(len: usize) -> Vec<String> {
+    (0..len).map(|i| format!("item {:03}", i)).collect()
+}
+
+#[test]
+fn every_item_reaches_the_floor() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut state = HumansortState::from(values(200));
+    state.set_min_appearances(3);
+    // At least two of the five items in each prompt go to items below the
+    // floor until there are none left.
+    for _ in 0..300 {
+        answer_prompt(&mut state, &mut rng);
+    }
+    let items = state.get_all_items();
+    assert!(
+        items.iter().all(|i| i.appearances() >= 3),
+        "{:?}",
+        items.iter().map(|i| i.appearances()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn merged_items_are_shown_right_away() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut state = HumansortState::from(values(50));
+    state.set_selection(Strategy::ActiveLearning(ActiveLearning));
+    for _ in 0..200 {
+        answer_prompt(&mut state, &mut rng);
+    }
+    let mut new_values = values(50);
+    new_values.push("item 050".to_string());
+    state.merge(&new_values);
+    let new_id = state.id_of("item 050").unwrap();
+    for _ in 0..state.min_appearances() {
+        let shown = state.next_with_rng(&mut rng).unwrap();
+        assert!(shown.contains(&new_id), "{:?}", shown);
+        state.judge(&shown, shown[0]).unwrap();
+    }
+}
+
+#[test]
+fn undo_takes_back_appearances() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut state = HumansortState::from(values(10));
+    let shown = state.next_with_rng(&mut rng).unwrap();
+    state.abstain(&shown).unwrap();
+    let total = |state: &HumansortState| -> u32 {
+        state.get_all_items().iter().map(|i| i.appearances()).sum()
+    };
+    assert_eq!(total(&state), shown.len() as u32);
+    state.undo().unwrap();
+    assert_eq!(total(&state), 0);
+}
@@ -0,0 +1,76 @@
+use humansort_lib::{HumansortState, ItemId};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+/// Items are sorted descending by rating, and looking them up by ID or text
+/// finds them.
+fn assert_consistent(state: &HumansortState) {
+    let items = state.get_all_items();
+    assert!(
+        items.windows(2).all(|w| w[0].rating() >= w[1].rating()),
+        "{:?}",
+        items.iter().map(|i| i.rating()).collect::<Vec<_>>()
+    );
+    for item in items.iter() {
+        assert_eq!(state.item(item.id()).unwrap().value(), item.value());
+        assert_eq!(state.id_of(item.value()).unwrap(), item.id());
+    }
+}
+
+#[test]
+fn lookups_and_order_survive_every_kind_of_change() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let values: Vec<String> = (0..30).map(|i| format!("item {}", i)).collect();
+    let mut state = HumansortState::from(values.clone());
+    let mut added = 0;
+    for step in 0..2000 {
+        match rng.gen_range(0..10) {
+            0 => {
+                added += 1;
+                state.add_item(&format!("new {}", added)).unwrap();
+            }
+            1 => {
+                let items = state.get_all_items();
+                let id = items.choose(&mut rng).unwrap().id();
+                state.remove_item(id).unwrap();
+            }
+            2 => {
+                let items = state.get_all_items();
+                let id = items.choose(&mut rng).unwrap().id();
+                state.rename_item(id, &format!("renamed {}", step)).unwrap();
+            }
+            3 => {
+                let _ = state.undo();
+            }
+            4 => {
+                let _ = state.redo();
+            }
+            5 => state.merge(&values),
+            _ => {
+                let shown = state.next_with_rng(&mut rng).unwrap();
+                let winner: ItemId = *shown.choose(&mut rng).unwrap();
+                state.judge(&shown, winner).unwrap();
+            }
+        }
+        assert_consistent(&state);
+    }
+}
+
+#[test]
+fn adding_an_item_twice_is_refused() {
+    let mut state = HumansortState::from(vec!["a".to_string()]);
+    assert!(state.add_item("a").is_err());
+    assert_eq!(state.get_all_items().len(), 1);
+}
+
+#[test]
+fn large_lists_stay_fast() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let values: Vec<String> = (0..50_000).map(|i| format!("item {}", i)).collect();
+    let mut state = HumansortState::from(values.clone());
+    state.merge(&values[1000..]);
+    for _ in 0..100 {
+        let shown = state.next_with_rng(&mut rng).unwrap();
+        state.judge(&shown, shown[0]).unwrap();
+    }
+    assert_consistent(&state);
+}
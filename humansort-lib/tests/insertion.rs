@@ -0,0 +1,133 @@
+mod common;
+
+use common::{answer_all, strings};
+use humansort_lib::{HumansortState, Insertion, ItemId, Session};
+
+/// A list of 16 items that's already in the right order, where the right
+/// order is alphabetical, followed by the same list with three items added.
+fn established() -> (HumansortState, Vec<String>) {
+    let all: Vec<String> = (0..19).map(|i| format!("item {:02}", i)).collect();
+    let old: Vec<String> = all
+        .iter()
+        .filter(|v| !["item 03", "item 08", "item 15"].contains(&v.as_str()))
+        .cloned()
+        .collect();
+    let mut state = HumansortState::from(old.clone());
+    let order: Vec<ItemId> = old.iter().map(|v| state.id_of(v).unwrap()).collect();
+    state.rank(&order, &order).unwrap();
+    (state, all)
+}
+
+#[test]
+fn new_items_are_placed_with_few_questions() {
+    let (mut state, all) = established();
+    let added = state.merge_with(&all, &Default::default()).unwrap();
+    assert_eq!(added.len(), 3);
+    let insertion = Insertion::new(&state, &added);
+    assert_eq!(insertion.ranking.len(), 16);
+    // Each item takes at most ceil(log2(n + 1)) questions.
+    assert_eq!(insertion.questions_left(&state), 5 + 5 + 5);
+    state.start_session(Session::Insertion(insertion));
+
+    let questions = answer_all(&mut state);
+    assert!(questions <= 15);
+    let order: Vec<&str> = state.ranked().map(|item| item.value).collect();
+    assert_eq!(order, all);
+}
+
+#[test]
+fn placed_items_get_ratings_between_their_neighbors() {
+    let (mut state, all) = established();
+    let added = state.merge_with(&all, &Default::default()).unwrap();
+    state.start_session(Session::Insertion(Insertion::new(&state, &added)));
+    answer_all(&mut state);
+
+    let ranked: Vec<_> = state.ranked().collect();
+    for id in added {
+        let rank = state.rank_of(id).unwrap();
+        let rating = state.rating_of(id).unwrap();
+        let above = ranked[rank - 2].rating;
+        let below = ranked[rank].rating;
+        assert!(
+            above > rating && rating > below,
+            "{} {} {}",
+            above,
+            rating,
+            below
+        );
+    }
+}
+
+#[test]
+fn undo_takes_back_a_placement() {
+    let (mut state, all) = established();
+    let added = state.merge_with(&all, &Default::default()).unwrap();
+    state.start_session(Session::Insertion(Insertion::new(&state, &added)));
+    let unplaced = state.rating_of(added[0]).unwrap();
+    let ratings: Vec<f32> = state.ranked().map(|item| item.rating).collect();
+    // Place the first item with every answer going its way.
+    let mut shown = state.session_prompt().unwrap();
+    while shown[0] == added[0] {
+        state.judge(&shown, added[0]).unwrap();
+        shown = state.session_prompt().unwrap();
+    }
+    assert_eq!(state.rank_of(added[0]), Some(1));
+    let top = state.ranked().nth(1).unwrap().rating;
+    assert!(state.rating_of(added[0]).unwrap() > top);
+
+    state.undo().unwrap();
+    // Nothing moved along the way either.
+    assert_eq!(state.rating_of(added[0]), Some(unplaced));
+    let now: Vec<f32> = state.ranked().map(|item| item.rating).collect();
+    assert_eq!(now, ratings);
+    assert_eq!(state.session_prompt().unwrap()[0], added[0]);
+}
+
+#[test]
+fn items_placed_at_the_top_stay_above_what_they_beat() {
+    let mut state = HumansortState::from(strings(&["b", "c", "d", "e"]));
+    let order: Vec<ItemId> = ["b", "c", "d", "e"]
+        .iter()
+        .map(|v| state.id_of(v).unwrap())
+        .collect();
+    state.rank(&order, &order).unwrap();
+    // Every rating left is above zero, where the new item starts.
+    state.merge(&strings(&["b", "c"]));
+    let added = state
+        .merge_with(&strings(&["a", "b", "c"]), &Default::default())
+        .unwrap();
+    state.start_session(Session::Insertion(Insertion::new(&state, &added)));
+    answer_all(&mut state);
+    let order: Vec<&str> = state.ranked().map(|item| item.value).collect();
+    assert_eq!(order, ["a", "b", "c"]);
+}
+
+#[test]
+fn contradicting_an_earlier_answer_takes_the_placement_back() {
+    let (mut state, all) = established();
+    let added = state.merge_with(&all, &Default::default()).unwrap();
+    state.start_session(Session::Insertion(Insertion::new(&state, &added)));
+    let mut asked = Vec::new();
+    while let Some(shown) = state.session_prompt().filter(|shown| shown[0] == added[0]) {
+        let value = |id| state.item(id).unwrap().value().to_string();
+        let winner = if value(shown[0]) < value(shown[1]) {
+            shown[0]
+        } else {
+            shown[1]
+        };
+        state.judge(&shown, winner).unwrap();
+        asked.push((shown, winner));
+    }
+    // Answer the first question again, the other way round, which sends the
+    // search down a path with questions that haven't been asked yet.
+    let (first, winner) = &asked[0];
+    let loser = if *winner == first[0] {
+        first[1]
+    } else {
+        first[0]
+    };
+    state.judge(first, loser).unwrap();
+    assert_eq!(state.session_prompt().unwrap()[0], added[0]);
+    answer_all(&mut state);
+    assert!(state.session_prompt().is_none());
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::strings;
+use humansort_lib::{HumansortError, HumansortState, MergeOptions, NewRating};
+
+/// "a" beats "b", "b" beats "c", and "c" beats "d".
+fn rated_state() -> HumansortState {
+    let mut state = HumansortState::from(strings(&["a", "b", "c", "d"]));
+    for (winner, loser) in [("a", "b"), ("b", "c"), ("c", "d"), ("a", "d")] {
+        let winner = state.id_of(winner).unwrap();
+        let loser = state.id_of(loser).unwrap();
+        state.judge(&[winner, loser], winner).unwrap();
+    }
+    state
+}
+
+fn ratings(state: &HumansortState) -> Vec<f32> {
+    state.ranked().map(|item| item.rating).collect()
+}
+
+#[test]
+fn plain_merges_forget_missing_items() {
+    let mut state = rated_state();
+    let b = state.id_of("b").unwrap();
+    state.merge(&strings(&["a", "c", "d"]));
+    state.merge(&strings(&["a", "b", "c", "d"]));
+    assert!(state.id_of("b").unwrap() != b);
+    assert_eq!(state.rating_of(state.id_of("b").unwrap()), Some(0.));
+    assert!(state.archived().is_empty());
+}
+
+#[test]
+fn archived_items_come_back_as_they_were() {
+    let mut state = rated_state();
+    let b = state.id_of("b").unwrap();
+    let rating = state.rating_of(b).unwrap();
+    let rank = state.rank_of(b);
+    let options = MergeOptions {
+        archive_missing: true,
+        ..Default::default()
+    };
+    state
+        .merge_with(&strings(&["a", "c", "d"]), &options)
+        .unwrap();
+    assert!(state.item(b).is_none());
+    assert_eq!(state.archived().len(), 1);
+
+    // The archive survives saving.
+    let mut state: HumansortState =
+        serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+    state
+        .merge_with(&strings(&["a", "b", "c", "d"]), &options)
+        .unwrap();
+    assert_eq!(state.id_of("b").unwrap(), b);
+    assert_eq!(state.rating_of(b), Some(rating));
+    assert_eq!(state.rank_of(b), rank);
+    assert!(state.archived().is_empty());
+}
+
+#[test]
+fn new_items_can_start_at_the_mean_median_or_a_prior() {
+    let values = strings(&["a", "b", "c", "d", "new"]);
+    let existing = ratings(&rated_state());
+    let mean = existing.iter().sum::<f32>() / 4.;
+    let median = (existing[1] + existing[2]) / 2.;
+    for (new_rating, expected) in [
+        (NewRating::Unrated, 0.),
+        (NewRating::Mean, mean),
+        (NewRating::Median, median),
+        (NewRating::Prior(2.5), 2.5),
+    ] {
+        let mut state = rated_state();
+        let options = MergeOptions {
+            new_rating,
+            ..Default::default()
+        };
+        state.merge_with(&values, &options).unwrap();
+        let new = state.id_of("new").unwrap();
+        assert_eq!(state.rating_of(new), Some(expected), "{:?}", new_rating);
+        // The ranking is still in order.
+        let ratings = ratings(&state);
+        assert!(ratings.windows(2).all(|w| w[0] >= w[1]), "{:?}", ratings);
+    }
+}
+
+#[test]
+fn priors_have_to_be_finite() {
+    for prior in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        let mut state = rated_state();
+        let before = ratings(&state);
+        let options = MergeOptions {
+            new_rating: NewRating::Prior(prior),
+            ..Default::default()
+        };
+        let result = state.merge_with(&strings(&["a", "new"]), &options);
+        assert!(matches!(result, Err(HumansortError::InvalidRating(_))));
+        // Nothing was merged.
+        assert_eq!(ratings(&state), before);
+        assert!(state.id_of("new").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use humansort_lib::{Elo, Glicko2, HumansortItem, HumansortState, Matchup, Model, RatingModel};
+
+fn item(rating: f32, deviation: f32) -> HumansortItem {
+    serde_json::from_value(serde_json::json!({
+        "value": "",
+        "rating": rating,
+        "deviation": deviation,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn elo_is_the_default_and_moves_ratings_by_the_expected_score() {
+    assert_eq!(
+        HumansortState::default().model(),
+        &Model::Elo(Elo::default())
+    );
+
+    // Evenly matched items expect half a point each.
+    let mut items = [item(0., 0.), item(0., 0.), item(0., 0.)];
+    let elo = Elo::default();
+    assert_eq!(elo.win_probability(&items[0], &items[1]), 0.5);
+    // Both wins are worked out from the ratings before the judgment.
+    elo.update(&mut items, &[Matchup::win(0, 1), Matchup::win(0, 2)]);
+    let ratings: Vec<f32> = items.iter().map(|i| i.rating()).collect();
+    assert_eq!(ratings, [1., -0.5, -0.5]);
+
+    // A draw pulls the two ratings together by as much as they'd move apart.
+    let mut items = [item(1., 0.), item(-1., 0.)];
+    let expected = elo.win_probability(&items[0], &items[1]);
+    elo.update(&mut items, &[Matchup::draw(0, 1)]);
+    assert_eq!(items[0].rating(), 1. + 0.5 - expected);
+    assert_eq!(items[1].rating(), -1. - 0.5 + expected);
+}
+
+#[test]
+fn glicko2_matches_glickmans_example() {
+    // The worked example from Glickman's "Example of the Glicko-2 system",
+    // shifted down by 1500 since ratings here are centered on zero.
+    let mut items = [
+        item(0., 200.),
+        item(-100., 30.),
+        item(50., 100.),
+        item(200., 300.),
+    ];
+    let matchups = [Matchup::win(0, 1), Matchup::win(2, 0), Matchup::win(3, 0)];
+    Glicko2 { tau: 0.5 }.update(&mut items, &matchups);
+    let player = &items[0];
+    assert!(
+        (player.rating() + 1500. - 1464.05).abs() < 0.01,
+        "{}",
+        player.rating()
+    );
+    assert!(
+        (player.deviation() - 151.52).abs() < 0.01,
+        "{}",
+        player.deviation()
+    );
+    assert!(
+        (player.volatility() - 0.05999).abs() < 0.00001,
+        "{}",
+        player.volatility()
+    );
+}
@@ -0,0 +1,63 @@
+use humansort_lib::{Glicko2, HumansortState, Model};
+
+/// Five items where "item 0" has beaten everything, "item 1" everything but
+/// "item 0", and so on.
+fn ranked_state() -> HumansortState {
+    let values: Vec<String> = (0..5).map(|i| format!("item {}", i)).collect();
+    let mut state = HumansortState::from(values);
+    state.set_model(Model::Glicko2(Glicko2::default()));
+    let ids: Vec<_> = (0..5)
+        .map(|i| state.id_of(&format!("item {}", i)).unwrap())
+        .collect();
+    for i in 0..4 {
+        state.judge(&ids[i..], ids[i]).unwrap();
+    }
+    state
+}
+
+#[test]
+fn ranked_walks_the_items_in_order_without_consuming_the_state() {
+    let state = ranked_state();
+    let values: Vec<&str> = state.ranked().map(|item| item.value).collect();
+    assert_eq!(values, ["item 0", "item 1", "item 2", "item 3", "item 4"]);
+    for (idx, item) in state.ranked().enumerate() {
+        assert_eq!(item.rank, idx + 1);
+        assert_eq!(state.rank_of(item.id), Some(item.rank));
+        assert_eq!(state.rating_of(item.id), Some(item.rating));
+        assert!(item.uncertainty.is_some());
+        assert!(item.comparisons > 0);
+    }
+    assert_eq!(state.ranked().len(), 5);
+}
+
+#[test]
+fn neighbors_stop_at_the_ends() {
+    let state = ranked_state();
+    let id = |value: &str| state.id_of(value).unwrap();
+    let values = |id, n| -> Vec<&str> {
+        state
+            .neighbors(id, n)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.value)
+            .collect()
+    };
+    assert_eq!(values(id("item 2"), 1), ["item 1", "item 3"]);
+    assert_eq!(values(id("item 0"), 2), ["item 1", "item 2"]);
+    assert_eq!(
+        values(id("item 4"), 10),
+        ["item 0", "item 1", "item 2", "item 3"]
+    );
+    assert!(values(id("item 2"), 0).is_empty());
+}
+
+#[test]
+fn removed_items_have_no_rank() {
+    let mut state = ranked_state();
+    let id = state.id_of("item 2").unwrap();
+    state.remove_item(id).unwrap();
+    assert_eq!(state.rank_of(id), None);
+    assert_eq!(state.rating_of(id), None);
+    assert!(state.neighbors(id, 1).is_none());
+    assert_eq!(state.rank_of(state.id_of("item 3").unwrap()), Some(3));
+}
@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+
+mod common;
+
+use common::strings;
+use humansort_lib::{similarity, HumansortState};
+
+#[test]
+fn similarity_counts_edits() {
+    assert_eq!(similarity("Dune", "Dune"), 1.);
+    assert_eq!(similarity("Snow Crash", "snow crash"), 1.);
+    assert_eq!(similarity("Foundation", "Fundation"), 0.9);
+    assert_eq!(similarity("abc", "xyz"), 0.);
+    assert_eq!(similarity("", ""), 1.);
+    assert_eq!(similarity("", "a"), 0.);
+}
+
+#[test]
+fn each_item_and_line_is_paired_at_most_once() {
+    let state = HumansortState::from(strings(&["Foundation", "Founders", "Dune"]));
+    let candidates = state.rename_candidates(
+        &strings(&["Fundation", "Foundations", "Dune", "Unrelated"]),
+        0.6,
+    );
+    let pairs: Vec<(&str, &str)> = candidates
+        .iter()
+        .map(|c| (c.old_value.as_str(), c.new_value.as_str()))
+        .collect();
+    assert_eq!(pairs[0], ("Foundation", "Foundations"));
+    assert!(pairs.iter().all(|&(old, _)| old != "Dune"));
+    assert!(pairs.iter().all(|&(_, new)| new != "Unrelated"));
+    let olds: HashSet<_> = pairs.iter().map(|p| p.0).collect();
+    let news: HashSet<_> = pairs.iter().map(|p| p.1).collect();
+    assert_eq!(olds.len(), pairs.len());
+    assert_eq!(news.len(), pairs.len());
+}
+
+#[test]
+fn renames_keep_ratings_and_history() {
+    let mut state = HumansortState::from(strings(&["Hte Hobbit", "Dune", "Neuromancer"]));
+    let hobbit = state.id_of("Hte Hobbit").unwrap();
+    let dune = state.id_of("Dune").unwrap();
+    state.judge(&[hobbit, dune], hobbit).unwrap();
+    let rating = state.rating_of(hobbit).unwrap();
+
+    let lines = strings(&["The Hobbit", "Dune", "Neuromancer"]);
+    for candidate in state.rename_candidates(&lines, 0.75) {
+        state
+            .rename_item(candidate.id, &candidate.new_value)
+            .unwrap();
+    }
+    state.merge(&lines);
+    assert_eq!(state.id_of("The Hobbit").unwrap(), hobbit);
+    assert_eq!(state.rating_of(hobbit), Some(rating));
+    assert_eq!(state.history().len(), 1);
+    assert_eq!(state.ranked().len(), 3);
+
+    // Without a rename, the edited line is a new item.
+    let mut state = HumansortState::from(strings(&["Hte Hobbit", "Dune"]));
+    let hobbit = state.id_of("Hte Hobbit").unwrap();
+    state.merge(&strings(&["The Hobbit", "Dune"]));
+    assert!(state.id_of("The Hobbit").unwrap() != hobbit);
+}
+
+#[test]
+fn renames_are_found_in_long_lists() {
+    let values: Vec<String> = (0..20_000).map(|i| format!("item number {}", i)).collect();
+    let state = HumansortState::from(values.clone());
+    // Fix a "typo" in every 40th line.
+    let mut edited = values.clone();
+    for value in edited.iter_mut().step_by(40) {
+        *value = value.replacen("number", "nmuber", 1);
+    }
+    let candidates = state.rename_candidates(&edited, 0.6);
+    assert_eq!(candidates.len(), 500);
+    assert!(candidates
+        .iter()
+        .all(|c| c.new_value == c.old_value.replacen("number", "nmuber", 1)));
+
+    // Very short text has pairs of characters to go on too.
+    let state = HumansortState::from(strings(&["ab", "x"]));
+    let candidates = state.rename_candidates(&strings(&["abc", "x"]), 0.6);
+    assert_eq!(candidates.len(), 1);
+}
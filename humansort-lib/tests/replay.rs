@@ -0,0 +1,69 @@
+mod common;
+
+use common::answer_prompt;
+use humansort_lib::{Elo, Glicko2, HumansortError, HumansortState, ItemId, Model};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// A state with a few dozen judgments under `model`, picking the item that
+/// sorts first by value each time.
+fn judged_state(model: Model) -> HumansortState {
+    let values: Vec<String> = (0..12).map(|i| format!("item {:02}", i)).collect();
+    let mut state = HumansortState::from(values);
+    state.set_model(model);
+    state.set_num_items(3).unwrap();
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..40 {
+        answer_prompt(&mut state, &mut rng);
+    }
+    state
+}
+
+fn ratings(state: &HumansortState) -> Vec<(ItemId, f32)> {
+    state.ranked().map(|item| (item.id, item.rating)).collect()
+}
+
+#[test]
+fn replaying_under_the_same_model_rebuilds_the_same_ratings() {
+    for model in [
+        Model::Elo(Elo::default()),
+        Model::Glicko2(Glicko2::default()),
+    ] {
+        let mut state = judged_state(model.clone());
+        let before = ratings(&state);
+        state.replay(model).unwrap();
+        assert_eq!(ratings(&state), before);
+        // Undo still has what it needs afterward.
+        state.undo().unwrap();
+    }
+}
+
+#[test]
+fn nonsense_model_parameters_are_refused() {
+    let mut state = judged_state(Model::default());
+    let before = ratings(&state);
+    for elo in [
+        Elo {
+            scale: 0.,
+            ..Default::default()
+        },
+        Elo {
+            k_factor: -1.,
+            ..Default::default()
+        },
+        Elo {
+            scale: f32::NAN,
+            ..Default::default()
+        },
+    ] {
+        assert!(matches!(
+            state.replay(Model::Elo(elo)),
+            Err(HumansortError::InvalidModel(_))
+        ));
+    }
+    assert!(matches!(
+        state.replay(Model::Glicko2(Glicko2 { tau: f32::INFINITY })),
+        Err(HumansortError::InvalidModel(_))
+    ));
+    assert_eq!(state.model(), &Model::default());
+    assert_eq!(ratings(&state), before);
+}
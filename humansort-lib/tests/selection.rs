@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+mod common;
+
+use common::answer_prompt;
+use humansort_lib::{
+    ActiveLearning, Elo, HumansortItem, HumansortState, Model, Quadratic, SelectionStrategy,
+    Strategy,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn items(len: usize) -> Vec<HumansortItem> {
+    let values: Vec<String> = (0..len).map(|i| i.to_string()).collect();
+    HumansortState::from(values).get_all_items()
+}
+
+fn assert_distinct_and_in_range(indices: &[usize], num_items: usize, len: usize) {
+    assert_eq!(indices.len(), num_items);
+    assert!(indices.iter().all(|&i| i < len), "{:?}", indices);
+    let unique: HashSet<_> = indices.iter().collect();
+    assert_eq!(unique.len(), num_items, "{:?}", indices);
+}
+
+#[test]
+fn quadratic_takes_every_item_when_asked_for_all_of_them() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for len in 2..=9 {
+        let items = items(len);
+        for _ in 0..1000 {
+            let indices = Quadratic.select(&items, &Model::default(), len, &mut rng);
+            assert_distinct_and_in_range(&indices, len, len);
+        }
+    }
+}
+
+#[test]
+fn quadratic_handles_one_less_than_all() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let items = items(9);
+    for _ in 0..1000 {
+        let indices = Quadratic.select(&items, &Model::default(), 8, &mut rng);
+        assert_distinct_and_in_range(&indices, 8, 9);
+    }
+}
+
+#[test]
+fn quadratic_handles_large_lists() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let items = items(100_000);
+    for num_items in [2, 5, 9, 1000, 100_000] {
+        let indices = Quadratic.select(&items, &Model::default(), num_items, &mut rng);
+        assert_distinct_and_in_range(&indices, num_items, items.len());
+    }
+}
+
+#[test]
+fn quadratic_favors_the_top_of_the_list() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let items = items(100);
+    let mut counts = vec![0; items.len()];
+    for _ in 0..10_000 {
+        for idx in Quadratic.select(&items, &Model::default(), 5, &mut rng) {
+            counts[idx] += 1;
+        }
+    }
+    let top: u32 = counts[..10].iter().sum();
+    let bottom: u32 = counts[90..].iter().sum();
+    assert!(top > 3 * bottom, "top {} vs bottom {}", top, bottom);
+    // Every item should still come up now and then.
+    assert!(counts.iter().all(|&c| c > 0), "{:?}", counts);
+}
+
+#[test]
+fn quadratic_matches_the_distribution_of_single_draws() {
+    // With one item per prompt, index `i` comes up with probability
+    // `sqrt((i + 1) / len) - sqrt(i / len)`.
+    let mut rng = StdRng::seed_from_u64(4);
+    let items = items(4);
+    let draws = 100_000;
+    let mut counts = [0_f64; 4];
+    for _ in 0..draws {
+        counts[Quadratic.select(&items, &Model::default(), 1, &mut rng)[0]] += 1.;
+    }
+    for (i, count) in counts.iter().enumerate() {
+        let expected = ((i + 1) as f64 / 4.).sqrt() - (i as f64 / 4.).sqrt();
+        assert!(
+            (count / draws as f64 - expected).abs() < 0.01,
+            "index {}: {} vs {}",
+            i,
+            count / draws as f64,
+            expected
+        );
+    }
+}
+
+#[test]
+fn next_shows_every_item_when_the_prompt_is_as_big_as_the_list() {
+    let values: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+    let mut state = HumansortState::from(values);
+    state.set_model(Model::Elo(Elo::default()));
+    state.set_selection(Strategy::Quadratic(Quadratic));
+    let all: Vec<_> = state.get_all_items().iter().map(|i| i.id()).collect();
+    for _ in 0..100 {
+        let shown = state.next().unwrap();
+        assert_eq!(
+            shown.iter().collect::<HashSet<_>>(),
+            all.iter().collect::<HashSet<_>>()
+        );
+        state.judge(&all, all[0]).unwrap();
+    }
+}
+
+#[test]
+fn next_with_the_same_seed_gives_the_same_items() {
+    let values: Vec<String> = (0..1000).map(|i| i.to_string()).collect();
+    let state = HumansortState::from(values);
+    let first = state.next_with_rng(&mut StdRng::seed_from_u64(5)).unwrap();
+    let second = state.next_with_rng(&mut StdRng::seed_from_u64(5)).unwrap();
+    assert_eq!(first, second);
+}
+
+/// How far, on average, each item ends up from its true rank after `prompts`
+/// answers from a consistent user, whose true order is the order of the
+/// items' text.
+fn mean_rank_error(selection: Strategy, prompts: usize) -> f64 {
+    let values: Vec<String> = (0..200).map(|i| format!("item {:03}", i)).collect();
+    let mut state = HumansortState::from(values);
+    state.set_selection(selection);
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..prompts {
+        answer_prompt(&mut state, &mut rng);
+    }
+    let error: usize = state
+        .ranked()
+        .enumerate()
+        .map(|(rank, item)| rank.abs_diff(item.value[5..].parse().unwrap()))
+        .sum();
+    error as f64 / 200.
+}
+
+#[test]
+fn active_learning_ranks_better_than_quadratic_for_the_same_effort() {
+    let active = mean_rank_error(Strategy::ActiveLearning(ActiveLearning), 500);
+    let quadratic = mean_rank_error(Strategy::Quadratic(Quadratic), 500);
+    // Quadratic ends up about four times as far off.
+    assert!(active * 3. < quadratic, "{} vs {}", active, quadratic);
+}
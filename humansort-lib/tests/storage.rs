@@ -0,0 +1,314 @@
+mod common;
+
+use common::first_by_text;
+use humansort_lib::{
+    Glicko2, HumansortError, HumansortState, MergeOptions, Model, Outcome, Session, Strategy, TopK,
+    FORMAT_VERSION,
+};
+
+/// A file from before items had IDs, whose history mentions an item ("gone")
+/// that has since been removed.
+const OLD_FILE: &str = r#"{
+  "items": [
+    { "value": "a", "rating": 0.5, "comparisons": 2 },
+    { "value": "b", "rating": -0.25, "comparisons": 1 },
+    { "value": "c", "rating": -0.25, "comparisons": 1 }
+  ],
+  "model": { "kind": "elo", "k_factor": 1.0, "scale": 1.0 },
+  "history": [
+    {
+      "shown": ["a", "b", "gone"],
+      "outcome": { "kind": "winner", "item": "a" },
+      "timestamp": 1,
+      "before": [
+        { "value": "a", "rating": 0.0 },
+        { "value": "b", "rating": 0.0 },
+        { "value": "gone", "rating": 0.0 }
+      ]
+    },
+    {
+      "shown": ["c", "a"],
+      "outcome": { "kind": "ranking", "order": ["a"] },
+      "timestamp": 2,
+      "before": [
+        { "value": "c", "rating": 0.0 },
+        { "value": "a", "rating": 0.25 }
+      ]
+    }
+  ]
+}"#;
+
+#[test]
+fn old_files_get_ids_for_items_and_history() {
+    let state: HumansortState = serde_json::from_str(OLD_FILE).unwrap();
+    let a = state.id_of("a").unwrap();
+    let b = state.id_of("b").unwrap();
+    let c = state.id_of("c").unwrap();
+    assert!(a != b && b != c && a != c);
+
+    let history = state.history();
+    assert_eq!(history[0].outcome, Outcome::Winner { item: a });
+    assert_eq!(&history[0].shown[..2], &[a, b]);
+    // The removed item gets an ID that no current item has.
+    let gone = history[0].shown[2];
+    assert!(state.item(gone).is_none());
+    assert!(![a, b, c].contains(&gone));
+    assert_eq!(history[1].shown, vec![c, a]);
+    assert_eq!(history[1].outcome, Outcome::Ranking { order: vec![a] });
+}
+
+#[test]
+fn old_files_survive_a_round_trip() {
+    let state: HumansortState = serde_json::from_str(OLD_FILE).unwrap();
+    let saved = serde_json::to_string(&state).unwrap();
+    let reloaded: HumansortState = serde_json::from_str(&saved).unwrap();
+    assert!(state == reloaded);
+}
+
+#[test]
+fn new_items_never_reuse_ids() {
+    let mut state: HumansortState = serde_json::from_str(OLD_FILE).unwrap();
+    let gone = state.history()[0].shown[2];
+    let d = state.add_item("d").unwrap();
+    assert!(d != gone);
+    // Not even after a round trip.
+    let mut state: HumansortState =
+        serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+    let e = state.add_item("e").unwrap();
+    assert!(e != gone && e != d);
+}
+
+#[test]
+fn undo_works_on_old_history() {
+    let mut state: HumansortState = serde_json::from_str(OLD_FILE).unwrap();
+    state.undo().unwrap();
+    state.undo().unwrap();
+    for item in state.get_all_items() {
+        assert_eq!(item.rating(), 0., "{}", item);
+    }
+}
+
+#[test]
+fn renaming_keeps_history_and_refuses_duplicates() {
+    let mut state: HumansortState = serde_json::from_str(OLD_FILE).unwrap();
+    let a = state.id_of("a").unwrap();
+    state.rename_item(a, "apple").unwrap();
+    assert!(state.id_of("a").is_err());
+    assert_eq!(state.id_of("apple").unwrap(), a);
+    assert_eq!(state.history()[0].outcome, Outcome::Winner { item: a });
+    assert!(state.rename_item(a, "b").is_err());
+    assert_eq!(state.item(a).unwrap().value(), "apple");
+}
+
+#[test]
+fn the_very_first_format_still_loads() {
+    // Just items, with nothing but their text and rating.
+    let state: HumansortState = serde_json::from_str(
+        r#"{ "items": [{ "value": "x", "rating": -3.0 }, { "value": "y", "rating": 4.5 }] }"#,
+    )
+    .unwrap();
+    let values: Vec<_> = state
+        .get_all_items()
+        .iter()
+        .map(|i| i.to_string())
+        .collect();
+    assert_eq!(values, vec!["y", "x"]);
+    assert_eq!(state.item(state.id_of("x").unwrap()).unwrap().rating(), -3.);
+}
+
+#[test]
+fn old_items_count_as_already_shown() {
+    let state: HumansortState = serde_json::from_str(OLD_FILE).unwrap();
+    let appearances = |v| state.item(state.id_of(v).unwrap()).unwrap().appearances();
+    assert_eq!(
+        [appearances("a"), appearances("b"), appearances("c")],
+        [2, 1, 1]
+    );
+
+    // Items rated before anything else was kept are treated as calibrated.
+    let state: HumansortState = serde_json::from_str(
+        r#"{ "items": [{ "value": "x", "rating": -3.0 }, { "value": "y", "rating": 0.0 }] }"#,
+    )
+    .unwrap();
+    let appearances = |v| state.item(state.id_of(v).unwrap()).unwrap().appearances();
+    assert_eq!(appearances("x"), state.min_appearances());
+    assert_eq!(appearances("y"), 0);
+}
+
+#[test]
+fn saved_files_have_a_version() {
+    let state: HumansortState = serde_json::from_str(OLD_FILE).unwrap();
+    let saved = serde_json::to_value(&state).unwrap();
+    assert_eq!(saved["version"], FORMAT_VERSION);
+    // Since version 1, files refer to items by ID.
+    assert!(saved["history"][0]["shown"][0].is_u64());
+}
+
+#[test]
+fn newer_files_are_refused_clearly() {
+    // Even if the rest of the file makes no sense to this version.
+    let newer = r#"{ "version": 99, "items": "something else entirely" }"#;
+    let error = serde_json::from_str::<HumansortState>(newer)
+        .err()
+        .unwrap()
+        .to_string();
+    let expected = HumansortError::NewerFormat {
+        version: 99,
+        supported: FORMAT_VERSION,
+    }
+    .to_string();
+    assert!(error.starts_with(&expected), "{}", error);
+}
+
+#[test]
+fn version_1_settings_move_into_their_own_section() {
+    let state: HumansortState = serde_json::from_str(
+        r#"{
+          "version": 1,
+          "items": [{ "id": 1, "value": "x", "rating": 0.0 }, { "id": 2, "value": "y", "rating": 0.0 }],
+          "model": { "kind": "glicko2", "tau": 0.3 },
+          "history": [],
+          "seed": 7,
+          "min_appearances": 1,
+          "next_id": 2
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(state.model(), &Model::Glicko2(Glicko2 { tau: 0.3 }));
+    assert_eq!(state.seed(), Some(7));
+    assert_eq!(state.min_appearances(), 1);
+    // Settings that version 1 didn't save get their defaults.
+    assert_eq!(state.num_items(), 5);
+    assert_eq!(state.question(), None);
+}
+
+#[test]
+fn version_2_files_load_with_nothing_archived() {
+    let mut state: HumansortState = serde_json::from_str(
+        r#"{
+          "version": 2,
+          "items": [{ "id": 1, "value": "x", "rating": 1.0 }, { "id": 2, "value": "y", "rating": 0.0 }],
+          "settings": { "num_items": 2, "question": "Which is better?" },
+          "history": [],
+          "next_id": 2
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(state.num_items(), 2);
+    assert_eq!(state.question(), Some("Which is better?"));
+    assert!(state.archived().is_empty());
+    assert!(state.session().is_none());
+    // Items dropped from now on can be archived.
+    let options = MergeOptions {
+        archive_missing: true,
+        ..Default::default()
+    };
+    state.merge_with(&["y".to_string()], &options).unwrap();
+    assert_eq!(state.archived().len(), 1);
+}
+
+#[test]
+fn version_3_files_keep_their_archive_and_have_no_session() {
+    let state: HumansortState = serde_json::from_str(
+        r#"{
+          "version": 3,
+          "items": [{ "id": 2, "value": "y", "rating": 0.0 }],
+          "settings": {},
+          "history": [],
+          "archived": [{ "id": 1, "value": "x", "rating": 1.5 }],
+          "next_id": 2
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(state.archived().len(), 1);
+    assert_eq!(state.archived()[0].rating(), 1.5);
+    assert_eq!(state.session(), None::<&Session>);
+    let saved = serde_json::to_value(&state).unwrap();
+    assert_eq!(saved["version"], FORMAT_VERSION);
+}
+
+#[test]
+fn version_4_judgments_with_whole_items_still_undo() {
+    let mut state: HumansortState = serde_json::from_str(
+        r#"{
+          "version": 4,
+          "items": [
+            { "id": 1, "value": "x", "rating": 0.5, "comparisons": 1, "appearances": 1 },
+            { "id": 2, "value": "y", "rating": -0.5, "comparisons": 1, "appearances": 1 }
+          ],
+          "settings": { "num_items": 2 },
+          "history": [
+            {
+              "shown": [1, 2],
+              "outcome": { "kind": "winner", "item": 1 },
+              "timestamp": 1,
+              "before": [
+                { "id": 1, "value": "x", "rating": 0.0, "deviation": 350.0,
+                  "volatility": 0.06, "comparisons": 0, "appearances": 0 },
+                { "id": 2, "value": "y", "rating": 0.0, "deviation": 350.0,
+                  "volatility": 0.06, "comparisons": 0, "appearances": 0 }
+              ]
+            }
+          ],
+          "next_id": 2
+        }"#,
+    )
+    .unwrap();
+    state.undo().unwrap();
+    for item in state.get_all_items() {
+        assert_eq!(item.rating(), 0.);
+        assert_eq!(item.appearances(), 0);
+    }
+}
+
+#[test]
+fn judgments_dont_repeat_the_items_text() {
+    let long = "an item whose text goes on and on ".repeat(20);
+    let mut state = HumansortState::from(vec![long.clone(), "short".to_string()]);
+    let shown: Vec<_> = state.ranked().map(|item| item.id).collect();
+    state.judge(&shown, shown[0]).unwrap();
+    let saved = serde_json::to_string(&state).unwrap();
+    assert_eq!(saved.matches(&long).count(), 1);
+
+    // What's left is still enough to undo the judgment after a reload.
+    let mut reloaded: HumansortState = serde_json::from_str(&saved).unwrap();
+    reloaded.undo().unwrap();
+    assert!(reloaded.ranked().all(|item| item.rating == 0.));
+}
+
+#[test]
+fn seeded_runs_save_the_same_file() {
+    let play = || {
+        let values: Vec<String> = (0..20).map(|i| format!("item {:02}", i)).collect();
+        let mut state = HumansortState::from(values);
+        state.set_seed(Some(5));
+        for _ in 0..30 {
+            let shown = state.next().unwrap();
+            state.judge(&shown, first_by_text(&state, &shown)).unwrap();
+        }
+        serde_json::to_string(&state).unwrap()
+    };
+    let first = play();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    assert_eq!(first, play());
+}
+
+#[test]
+fn settings_are_saved() {
+    let mut state: HumansortState = serde_json::from_str(OLD_FILE).unwrap();
+    state.set_num_items(3).unwrap();
+    state.set_selection(Strategy::TopK(TopK { k: 2 }));
+    state.set_question(Some("Which would you rather eat?".to_string()));
+    let reloaded: HumansortState =
+        serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+    assert_eq!(reloaded.num_items(), 3);
+    assert_eq!(reloaded.selection(), &Strategy::TopK(TopK { k: 2 }));
+    assert_eq!(reloaded.question(), Some("Which would you rather eat?"));
+    assert!(state == reloaded);
+}
+
+#[test]
+fn bad_settings_are_refused() {
+    let file = r#"{ "version": 2, "items": [], "settings": { "num_items": 1 }, "next_id": 0 }"#;
+    assert!(serde_json::from_str::<HumansortState>(file).is_err());
+}
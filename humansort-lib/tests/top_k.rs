@@ -0,0 +1,52 @@
+use std::collections::HashSet;
+
+mod common;
+
+use common::answer_prompt;
+use humansort_lib::{Glicko2, HumansortState, Model, Strategy, TopK};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// A list whose true order is the order of the items' text, set up to find
+/// the best 10.
+fn top_10_of_300(model: Model) -> (HumansortState, HashSet<String>) {
+    let values: Vec<String> = (0..300).map(|i| format!("item {:03}", i)).collect();
+    let mut state = HumansortState::from(values.clone());
+    state.set_model(model);
+    state.set_selection(Strategy::TopK(TopK { k: 10 }));
+    (state, values.into_iter().take(10).collect())
+}
+
+fn top_10(state: &HumansortState) -> HashSet<String> {
+    state
+        .ranked()
+        .take(10)
+        .map(|i| i.value.to_string())
+        .collect()
+}
+
+#[test]
+fn a_settled_top_k_is_the_true_top_k() {
+    let (mut state, expected) = top_10_of_300(Model::Glicko2(Glicko2::default()));
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut judgments = 0;
+    while !state.top_k_settled(10) {
+        answer_prompt(&mut state, &mut rng);
+        judgments += 1;
+        assert!(judgments < 3000, "still not settled");
+    }
+    assert_eq!(top_10(&state), expected);
+}
+
+#[test]
+fn elo_never_settles_on_the_wrong_items() {
+    // Elo is confident about items it has seen little of, which used to make
+    // a wrong set look settled within a few hundred judgments.
+    let (mut state, expected) = top_10_of_300(Model::default());
+    let mut rng = StdRng::seed_from_u64(2);
+    for _ in 0..1000 {
+        answer_prompt(&mut state, &mut rng);
+        if state.top_k_settled(10) {
+            assert_eq!(top_10(&state), expected);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use humansort_lib::{Bracket, HumansortState, ItemId, RoundRobin, Session, Swiss};
+
+/// A list whose right order is alphabetical, with its IDs in that order.
+fn entrants(n: usize) -> (HumansortState, Vec<ItemId>) {
+    let values: Vec<String> = (0..n).map(|i| format!("item {:02}", i)).collect();
+    let state = HumansortState::from(values.clone());
+    let ids = values.iter().map(|v| state.id_of(v).unwrap()).collect();
+    (state, ids)
+}
+
+/// Play out the session, letting `winner` decide each game, and return the
+/// games in the order they were played.
+fn play(
+    state: &mut HumansortState,
+    mut winner: impl FnMut(&HumansortState, ItemId, ItemId) -> ItemId,
+) -> Vec<[ItemId; 2]> {
+    let mut games = Vec::new();
+    while let Some(shown) = state.session_prompt() {
+        let won = winner(state, shown[0], shown[1]);
+        state.judge(&shown, won).unwrap();
+        games.push([shown[0], shown[1]]);
+    }
+    games
+}
+
+/// The alphabetically earlier item wins.
+fn better(state: &HumansortState, a: ItemId, b: ItemId) -> ItemId {
+    match state.item(a).unwrap().value() < state.item(b).unwrap().value() {
+        true => a,
+        false => b,
+    }
+}
+
+fn standings(state: &HumansortState) -> Vec<(ItemId, f32)> {
+    state
+        .session()
+        .unwrap()
+        .tournament(state)
+        .unwrap()
+        .standings
+        .into_iter()
+        .map(|s| (s.id, s.points))
+        .collect()
+}
+
+#[test]
+fn round_robin_plays_every_pair_once() {
+    let (mut state, ids) = entrants(5);
+    state.start_session(Session::RoundRobin(RoundRobin::new(&state, &ids)));
+    assert_eq!(state.session().unwrap().questions_left(&state), 10);
+    let games = play(&mut state, better);
+    let pairs: HashSet<(ItemId, ItemId)> =
+        games.iter().map(|&[a, b]| (a.min(b), a.max(b))).collect();
+    assert_eq!(games.len(), 10);
+    assert_eq!(pairs.len(), 10);
+    let expected: Vec<(ItemId, f32)> = ids.iter().copied().zip([4., 3., 2., 1., 0.]).collect();
+    assert_eq!(standings(&state), expected);
+    // The results went into the ratings too.
+    let ranked: Vec<ItemId> = state.ranked().map(|item| item.id).collect();
+    assert_eq!(ranked, ids);
+}
+
+#[test]
+fn tournaments_resume_from_a_saved_file() {
+    let (mut state, ids) = entrants(6);
+    state.start_session(Session::RoundRobin(RoundRobin::new(&state, &ids)));
+    for _ in 0..7 {
+        let shown = state.session_prompt().unwrap();
+        let won = better(&state, shown[0], shown[1]);
+        state.judge(&shown, won).unwrap();
+    }
+    // A skip leaves the game to be played again.
+    let shown = state.session_prompt().unwrap();
+    state.abstain(&shown).unwrap();
+    assert_eq!(state.session_prompt(), Some(shown));
+
+    let saved = serde_json::to_string(&state).unwrap();
+    let mut state: HumansortState = serde_json::from_str(&saved).unwrap();
+    assert_eq!(state.session().unwrap().questions_left(&state), 8);
+    play(&mut state, better);
+    assert_eq!(standings(&state)[0], (ids[0], 5.));
+}
+
+#[test]
+fn swiss_avoids_rematches() {
+    let (mut state, ids) = entrants(8);
+    let swiss = Swiss::new(&state, &ids);
+    assert_eq!(swiss.rounds, 3);
+    state.start_session(Session::Swiss(swiss));
+    let games = play(&mut state, better);
+    assert_eq!(games.len(), 12);
+    let pairs: HashSet<(ItemId, ItemId)> =
+        games.iter().map(|&[a, b]| (a.min(b), a.max(b))).collect();
+    assert_eq!(pairs.len(), 12);
+    let standings = standings(&state);
+    assert_eq!(standings[0], (ids[0], 3.));
+    assert_eq!(standings[7], (ids[7], 0.));
+}
+
+#[test]
+fn single_elimination_gives_top_seeds_byes() {
+    let (mut state, ids) = entrants(6);
+    state.start_session(Session::Bracket(Bracket::new(&state, &ids, false)));
+    assert_eq!(state.session().unwrap().questions_left(&state), 5);
+    let games = play(&mut state, better);
+    assert_eq!(games.len(), 5);
+    // The top two seeds skip the first round.
+    for game in &games[..2] {
+        assert!(!game.contains(&ids[0]) && !game.contains(&ids[1]));
+    }
+    // The top two seeds meet in the final.
+    assert!(games[4].contains(&ids[0]) && games[4].contains(&ids[1]));
+    // After a bye, the winner only played twice.
+    let standings = standings(&state);
+    assert_eq!(standings[0], (ids[0], 2.));
+    assert_eq!(standings[1].0, ids[1]);
+}
+
+#[test]
+fn double_elimination_survives_one_upset() {
+    let (mut state, ids) = entrants(4);
+    state.start_session(Session::Bracket(Bracket::new(&state, &ids, true)));
+    // The best item loses its first game, and wins every other.
+    let mut upset = false;
+    let games = play(&mut state, |state, a, b| {
+        let won = better(state, a, b);
+        if won == ids[0] && !upset {
+            upset = true;
+            return if won == a { b } else { a };
+        }
+        won
+    });
+    assert!(upset);
+    // Every game has a loser: the others lose twice, the winner at most once.
+    assert!(games.len() <= 7);
+    assert_eq!(standings(&state)[0].0, ids[0]);
+}
+
+#[test]
+fn brackets_ask_again_after_a_tie() {
+    let (mut state, ids) = entrants(4);
+    state.start_session(Session::Bracket(Bracket::new(&state, &ids, false)));
+    let shown = state.session_prompt().unwrap();
+    state.tie(&shown).unwrap();
+    assert_eq!(state.session_prompt(), Some(shown));
+}
+
+#[test]
+fn undoing_past_the_start_keeps_the_tournament_going() {
+    let (mut state, ids) = entrants(3);
+    state.judge(&[ids[0], ids[1]], ids[0]).unwrap();
+    state.start_session(Session::RoundRobin(RoundRobin::new(&state, &ids)));
+    state.undo().unwrap();
+    let shown = state.session_prompt().unwrap();
+    state
+        .judge(&shown, better(&state, shown[0], shown[1]))
+        .unwrap();
+    assert_ne!(state.session_prompt(), Some(shown));
+    assert_eq!(state.session().unwrap().questions_left(&state), 2);
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::strings;
+use humansort_lib::{Glicko2, HumansortItem, HumansortState, Model};
+
+/// Every item, in ID order so that items with equal ratings compare the same
+/// whichever order they're ranked in.
+fn items(state: &HumansortState) -> Vec<HumansortItem> {
+    let mut items = state.get_all_items();
+    items.sort_by_key(|item| item.id());
+    items
+}
+
+#[test]
+fn undo_restores_every_item_exactly_and_redo_puts_it_back() {
+    for model in [Model::default(), Model::Glicko2(Glicko2::default())] {
+        let mut state = HumansortState::from(strings(&["a", "b", "c", "d"]));
+        state.set_model(model);
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|v| state.id_of(v).unwrap());
+
+        let mut snapshots = vec![items(&state)];
+        state.judge(&[a, b, c], c).unwrap();
+        snapshots.push(items(&state));
+        state.rank(&[b, d], &[d, b]).unwrap();
+        snapshots.push(items(&state));
+        state.tie(&[a, d]).unwrap();
+        snapshots.push(items(&state));
+
+        let last = snapshots.pop().unwrap();
+        while let Some(snapshot) = snapshots.pop() {
+            state.undo().unwrap();
+            assert_eq!(items(&state), snapshot);
+        }
+        assert!(!state.can_undo());
+        while state.can_redo() {
+            state.redo().unwrap();
+        }
+        assert_eq!(items(&state), last);
+        assert_eq!(state.history().len(), 3);
+    }
+}
+
+#[test]
+fn a_new_judgment_clears_what_was_undone() {
+    let mut state = HumansortState::from(strings(&["a", "b"]));
+    let [a, b] = ["a", "b"].map(|v| state.id_of(v).unwrap());
+    state.judge(&[a, b], a).unwrap();
+    state.undo().unwrap();
+    assert!(state.can_redo());
+    state.judge(&[a, b], b).unwrap();
+    assert!(!state.can_redo());
+}
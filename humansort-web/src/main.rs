@@ -1,15 +1,21 @@
 use std::rc::Rc;
 
-use gloo::storage::{LocalStorage, Storage};
-use humansort_lib::HumansortState;
+use gloo::storage::{errors::StorageError, LocalStorage, Storage};
+use humansort_lib::{
+    Bracket, Elo, ExactSort, ExactStep, Glicko2, HumansortError, HumansortState, Insertion, ItemId,
+    Model, RoundRobin, Session, Strategy, Swiss, TopK,
+};
 use serde::{Deserialize, Serialize};
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
 #[derive(PartialEq, Serialize, Deserialize)]
 struct AppState {
     current_view: AppView,
     humansort_state: HumansortState,
+    /// Why the last action didn't work, if it didn't.
+    #[serde(skip)]
+    error: Option<String>,
 }
 
 #[derive(PartialEq, Clone, Serialize, Deserialize)]
@@ -20,18 +26,84 @@ enum AppView {
 }
 
 enum Action {
-    AddItem { name: String },
-    RenameItem { old_name: String, new_name: String },
-    RemoveItem { name: String },
-    SelectPreference { winner: String, others: Vec<String> },
-    ChangeView { new_view: AppView },
+    AddItem {
+        name: String,
+    },
+    RenameItem {
+        id: ItemId,
+        new_name: String,
+    },
+    RemoveItem {
+        id: ItemId,
+    },
+    SelectPreference {
+        shown: Vec<ItemId>,
+        winner: ItemId,
+    },
+    SelectLoser {
+        shown: Vec<ItemId>,
+        loser: ItemId,
+    },
+    RankItems {
+        shown: Vec<ItemId>,
+        order: Vec<ItemId>,
+    },
+    Tie {
+        shown: Vec<ItemId>,
+    },
+    Abstain {
+        shown: Vec<ItemId>,
+    },
+    Undo,
+    Redo,
+    ChangeView {
+        new_view: AppView,
+    },
+    SetModel {
+        model: Model,
+    },
+    SetSelection {
+        selection: Strategy,
+    },
+    SetNumItems {
+        num_items: usize,
+    },
+    SetQuestion {
+        question: Option<String>,
+    },
+    StartSession {
+        session: Session,
+    },
+    EndSession,
+    DismissError,
 }
 
 impl AppState {
     const STORAGE_KEY: &str = "humansort_app_state";
 
+    /// Where saved data that couldn't be loaded is kept, so that it isn't
+    /// overwritten and lost.
+    const BACKUP_KEY: &str = "humansort_app_state_backup";
+
     fn load_or_default() -> AppState {
-        LocalStorage::get(Self::STORAGE_KEY).unwrap_or_default()
+        match LocalStorage::get::<AppState>(Self::STORAGE_KEY) {
+            Ok(state) => state,
+            Err(StorageError::KeyNotFound(_)) => AppState::default(),
+            Err(e) => {
+                if let Ok(raw) = LocalStorage::get::<serde_json::Value>(Self::STORAGE_KEY) {
+                    let _ = LocalStorage::set(Self::BACKUP_KEY, raw);
+                }
+                AppState {
+                    error: Some(format!(
+                        "Your saved list couldn't be loaded, so a new one was started. The old \
+                         one was kept under \"{}\" in your browser's local storage. ({})",
+                        Self::BACKUP_KEY,
+                        e
+                    )),
+                    ..Default::default()
+                }
+            }
+        }
     }
     fn store(&self) {
         let _ = LocalStorage::set(Self::STORAGE_KEY, self);
@@ -43,6 +115,7 @@ impl Default for AppState {
         AppState {
             current_view: AppView::Input,
             humansort_state: HumansortState::default(),
+            error: None,
         }
     }
 }
@@ -51,44 +124,56 @@ impl Reducible for AppState {
     type Action = Action;
 
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
-        let new_state = match action {
-            Action::AddItem { name } => {
-                let mut humansort_state = self.humansort_state.clone();
-                humansort_state.add_item(&name);
-                AppState {
-                    current_view: self.current_view.clone(),
-                    humansort_state,
-                }
+        let mut current_view = self.current_view.clone();
+        let mut humansort_state = self.humansort_state.clone();
+        let result = match action {
+            Action::AddItem { name } => humansort_state.add_item(&name).map(|_| ()),
+            Action::RenameItem { id, new_name } => humansort_state.rename_item(id, &new_name),
+            Action::RemoveItem { id } => humansort_state.remove_item(id),
+            Action::SelectPreference { shown, winner } => humansort_state.judge(&shown, winner),
+            Action::SelectLoser { shown, loser } => humansort_state.judge_loser(&shown, loser),
+            Action::Tie { shown } => humansort_state.tie(&shown),
+            Action::Abstain { shown } => humansort_state.abstain(&shown),
+            Action::RankItems { shown, order } => humansort_state.rank(&shown, &order),
+            Action::Undo => humansort_state.undo().map(|_| ()),
+            Action::Redo => humansort_state.redo().map(|_| ()),
+            Action::ChangeView { new_view } => {
+                current_view = new_view;
+                Ok(())
             }
-            Action::RenameItem { old_name, new_name } => {
-                let mut humansort_state = self.humansort_state.clone();
-                humansort_state.rename_item(&old_name, &new_name).unwrap();
-                AppState {
-                    current_view: self.current_view.clone(),
-                    humansort_state,
-                }
+            // Rate everything again under the new model, as `recompute` does
+            // in the CLI, so that old and new ratings don't get mixed.
+            Action::SetModel { model } => humansort_state.replay(model),
+            Action::SetSelection { selection } => {
+                humansort_state.set_selection(selection);
+                Ok(())
             }
-            Action::RemoveItem { name } => {
-                let mut humansort_state = self.humansort_state.clone();
-                humansort_state.remove_item(&name).unwrap();
-                AppState {
-                    current_view: self.current_view.clone(),
-                    humansort_state,
-                }
+            Action::SetNumItems { num_items } => humansort_state.set_num_items(num_items),
+            Action::SetQuestion { question } => {
+                humansort_state.set_question(question);
+                Ok(())
             }
-            Action::SelectPreference { winner, others } => {
-                let mut preferences = others;
-                preferences.insert(0, winner);
-                let mut humansort_state = self.humansort_state.clone();
-                humansort_state.update(&preferences).unwrap();
-                AppState {
-                    current_view: self.current_view.clone(),
-                    humansort_state,
-                }
+            Action::StartSession { session } => {
+                humansort_state.start_session(session);
+                Ok(())
+            }
+            Action::EndSession => {
+                humansort_state.end_session();
+                Ok(())
             }
-            Action::ChangeView { new_view } => AppState {
-                current_view: new_view,
+            Action::DismissError => Ok(()),
+        };
+        let new_state = match result {
+            Ok(()) => AppState {
+                current_view,
+                humansort_state,
+                error: None,
+            },
+            // Nothing changes, but the user gets told why.
+            Err(e) => AppState {
+                current_view: self.current_view.clone(),
                 humansort_state: self.humansort_state.clone(),
+                error: Some(friendly_message(&e)),
             },
         };
         new_state.store();
@@ -96,25 +181,48 @@ impl Reducible for AppState {
     }
 }
 
+/// What to tell the user when something they tried didn't work.
+fn friendly_message(error: &HumansortError) -> String {
+    match error {
+        HumansortError::DuplicateItem(value) => format!("\"{}\" is already in the list.", value),
+        HumansortError::ItemNotFound(_) | HumansortError::ValueNotFound(_) => {
+            "That item isn't in the list anymore.".to_string()
+        }
+        HumansortError::NotEnoughItems { needed, found } => format!(
+            "There need to be at least {} items to sort, but there are only {}.",
+            needed, found
+        ),
+        HumansortError::InvalidJudgment(_) => {
+            "That choice couldn't be recorded. Please try again.".to_string()
+        }
+        HumansortError::InvalidNumItems(_) => {
+            "Each prompt has to show at least two items.".to_string()
+        }
+        HumansortError::InvalidModel(reason) => {
+            format!("That rating model can't be used: {}.", reason)
+        }
+        HumansortError::NothingToUndo => "There's nothing to undo.".to_string(),
+        HumansortError::NothingToRedo => "There's nothing to redo.".to_string(),
+        e => format!("Something went wrong: {}.", e),
+    }
+}
+
 #[derive(Properties, PartialEq)]
 struct InputItemProps {
     state: UseReducerHandle<AppState>,
+    id: ItemId,
     value: String,
 }
 
 #[function_component]
 fn InputItem(props: &InputItemProps) -> Html {
-    let InputItemProps { state, value } = props;
+    let InputItemProps { state, id, value } = props;
     let editing = use_state(|| false);
     let editing_value = use_state(|| value.to_string());
     let onremove = {
         let state = state.clone();
-        let value = value.clone();
-        Callback::from(move |_| {
-            state.dispatch(Action::RemoveItem {
-                name: value.to_string(),
-            })
-        })
+        let id = *id;
+        Callback::from(move |_| state.dispatch(Action::RemoveItem { id }))
     };
     let onedit = {
         let editing = editing.clone();
@@ -124,7 +232,7 @@ fn InputItem(props: &InputItemProps) -> Html {
     };
     let onkeypress = {
         let state = state.clone();
-        let old_value = value.clone();
+        let id = *id;
         let editing = editing.clone();
         let editing_value = editing_value.clone();
         move |e: KeyboardEvent| {
@@ -133,7 +241,7 @@ fn InputItem(props: &InputItemProps) -> Html {
             editing_value.set(new_value.clone());
             if e.key() == "Enter" {
                 state.dispatch(Action::RenameItem {
-                    old_name: old_value.to_string(),
+                    id,
                     new_name: new_value,
                 });
                 editing.set(false);
@@ -197,6 +305,35 @@ fn InputView(props: &ViewProps) -> Html {
             }
         }
     };
+    let onchange_model = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let target: HtmlSelectElement = e.target_unchecked_into();
+            let model = match target.value().as_str() {
+                "glicko2" => Model::Glicko2(Glicko2::default()),
+                _ => Model::Elo(Elo::default()),
+            };
+            state.dispatch(Action::SetModel { model });
+        })
+    };
+    let onchange_num_items = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let num_items = input.value().parse().unwrap_or(0);
+            state.dispatch(Action::SetNumItems { num_items });
+        })
+    };
+    let onchange_question = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let question = Some(input.value()).filter(|q| !q.trim().is_empty());
+            state.dispatch(Action::SetQuestion { question });
+        })
+    };
+    let is_glicko2 = matches!(state.humansort_state.model(), Model::Glicko2(_));
+    let num_items = state.humansort_state.num_items();
     html! {
         <div>
             <input
@@ -204,17 +341,50 @@ fn InputView(props: &ViewProps) -> Html {
                 placeholder={"Type a new item and press enter to add it"}
                 {onkeypress}
             />
+            <div>
+                <label>
+                    { "Rating model: " }
+                    <select onchange={onchange_model}>
+                        <option value="elo" selected={!is_glicko2}>{ "Elo" }</option>
+                        <option value="glicko2" selected={is_glicko2}>{ "Glicko-2" }</option>
+                    </select>
+                </label>
+            </div>
+            <div>
+                <label>
+                    { "Items per prompt: " }
+                    <input
+                        type="number"
+                        class={"target"}
+                        min="2"
+                        value={num_items.to_string()}
+                        onchange={onchange_num_items}
+                    />
+                </label>
+            </div>
+            <div>
+                <input
+                    type="text"
+                    placeholder={"Question to ask, such as \"Which would you rather read?\""}
+                    value={state.humansort_state.question().unwrap_or_default().to_string()}
+                    onchange={onchange_question}
+                />
+            </div>
             <div>
                 <button
                     onclick={change_view_sorting}
-                    disabled={state.humansort_state.get_all_items().len() < 5}>
+                    disabled={state.humansort_state.ranked().len() < num_items}>
                     { "Start sorting 🠖" }
                 </button>
             </div>
             <table class={"viewContent"}>
-                { for state.humansort_state.get_all_items().iter().map(|item|
+                { for state.humansort_state.ranked().map(|item|
                     html! {
-                        <InputItem state={props.state.clone()} value={item.to_string()} />
+                        <InputItem
+                            state={props.state.clone()}
+                            id={item.id}
+                            value={item.value.to_string()}
+                        />
                     }
                 ) }
             </table>
@@ -224,40 +394,48 @@ fn InputView(props: &ViewProps) -> Html {
 
 #[derive(Properties, PartialEq)]
 struct SortingItemProps {
-    winner: String,
-    others: Vec<String>,
-    items_to_sort_setter: UseStateSetter<Vec<String>>,
-    state: UseReducerHandle<AppState>,
+    id: ItemId,
+    value: String,
+    position: Option<usize>,
+    onselect: Callback<ItemId>,
 }
 
 #[function_component]
 fn SortingItem(props: &SortingItemProps) -> Html {
     let SortingItemProps {
-        winner,
-        others,
-        items_to_sort_setter,
-        state,
+        id,
+        value,
+        position,
+        onselect,
     } = props;
     let onclick = {
-        let state = state.clone();
-        let winner = winner.clone();
-        let others = others.clone();
-        let items_to_sort_setter = items_to_sort_setter.clone();
-        Callback::from(move |_| {
-            state.dispatch(Action::SelectPreference {
-                winner: winner.to_string(),
-                others: others.to_vec(),
-            });
-            items_to_sort_setter.set(state.humansort_state.next().unwrap());
-        })
+        let id = *id;
+        let onselect = onselect.clone();
+        Callback::from(move |_| onselect.emit(id))
     };
     html! {
         <div>
-            <button class={"sortingItem"} onclick={onclick}>{ winner }</button>
+            <button class={"sortingItem"} onclick={onclick}>
+                {
+                    match position {
+                        Some(p) => format!("#{} {}", p + 1, value),
+                        None => value.to_string(),
+                    }
+                }
+            </button>
         </div>
     }
 }
 
+/// How clicking on an item in the sorting view is interpreted.
+#[derive(PartialEq, Clone, Copy)]
+enum SortingMode {
+    Best,
+    Worst,
+    /// The user clicks items from best to worst.
+    Ranking,
+}
+
 #[function_component]
 fn SortingView(props: &ViewProps) -> Html {
     let ViewProps { state } = props;
@@ -277,26 +455,441 @@ fn SortingView(props: &ViewProps) -> Html {
             })
         })
     };
-    let items_to_sort = use_state(|| state.humansort_state.next().unwrap());
+    let undo = {
+        let state = state.clone();
+        Callback::from(move |_| state.dispatch(Action::Undo))
+    };
+    let redo = {
+        let state = state.clone();
+        Callback::from(move |_| state.dispatch(Action::Redo))
+    };
+    // Pick new items once each judgment, undo or redo has gone through, so
+    // that the choice sees the ratings and appearances it left behind.
+    let items_to_sort = use_state(Vec::<ItemId>::new);
+    {
+        let state = state.clone();
+        let items_to_sort = items_to_sort.clone();
+        let changes = (
+            state.humansort_state.history().len(),
+            state.humansort_state.ranked().len(),
+        );
+        use_effect_with_deps(
+            move |_| {
+                items_to_sort.set(state.humansort_state.next().unwrap_or_default());
+                || ()
+            },
+            changes,
+        );
+    }
+    // A session picks its own items from the latest state, so they're worked
+    // out afresh every time.
+    let session = state.humansort_state.session().cloned();
+    let shown_items = match session {
+        Some(_) => state.humansort_state.session_prompt().unwrap_or_default(),
+        None => (*items_to_sort).clone(),
+    };
+    let mode = use_state(|| SortingMode::Best);
+    let ranked = use_state(Vec::<ItemId>::new);
+    let onchange_mode = {
+        let mode = mode.clone();
+        let ranked = ranked.clone();
+        Callback::from(move |e: Event| {
+            let target: HtmlSelectElement = e.target_unchecked_into();
+            mode.set(match target.value().as_str() {
+                "worst" => SortingMode::Worst,
+                "ranking" => SortingMode::Ranking,
+                _ => SortingMode::Best,
+            });
+            ranked.set(Vec::new());
+        })
+    };
+    let tie = {
+        let state = state.clone();
+        let shown = shown_items.clone();
+        let ranked = ranked.clone();
+        Callback::from(move |_| {
+            state.dispatch(Action::Tie {
+                shown: shown.clone(),
+            });
+            ranked.set(Vec::new());
+        })
+    };
+    let abstain = {
+        let state = state.clone();
+        let shown = shown_items.clone();
+        let ranked = ranked.clone();
+        Callback::from(move |_| {
+            state.dispatch(Action::Abstain {
+                shown: shown.clone(),
+            });
+            ranked.set(Vec::new());
+        })
+    };
+    let submit_ranking = {
+        let state = state.clone();
+        let shown = shown_items.clone();
+        let ranked = ranked.clone();
+        Callback::from(move |_| {
+            state.dispatch(Action::RankItems {
+                shown: shown.clone(),
+                order: (*ranked).clone(),
+            });
+            ranked.set(Vec::new());
+        })
+    };
+    let clear_ranking = {
+        let ranked = ranked.clone();
+        Callback::from(move |_| ranked.set(Vec::new()))
+    };
+    let onselect = {
+        let state = state.clone();
+        let shown_items = shown_items.clone();
+        let mode = mode.clone();
+        let ranked = ranked.clone();
+        Callback::from(move |id: ItemId| {
+            let shown = shown_items.clone();
+            match *mode {
+                SortingMode::Best => {
+                    state.dispatch(Action::SelectPreference { shown, winner: id });
+                    return;
+                }
+                SortingMode::Worst => {
+                    state.dispatch(Action::SelectLoser { shown, loser: id });
+                    return;
+                }
+                SortingMode::Ranking => {}
+            }
+            let mut order = (*ranked).clone();
+            if order.contains(&id) {
+                return;
+            }
+            order.push(id);
+            // The last item's place is implied by the others.
+            if order.len() + 1 == shown.len() {
+                state.dispatch(Action::RankItems { shown, order });
+                ranked.set(Vec::new());
+            } else {
+                ranked.set(order);
+            }
+        })
+    };
+    // The user can pick how settled the ranking should be before they stop.
+    let target = use_state(|| None::<u32>);
+    let onchange_target = {
+        let target = target.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            target.set(input.value().parse().ok());
+        })
+    };
+    let settled = (state.humansort_state.convergence() * 100.).round() as u32;
+    let target_reached = target.is_some_and(|t| settled >= t);
+    // Alternatively, they can focus on which items belong in the top few.
+    let top_k = match state.humansort_state.selection() {
+        Strategy::TopK(TopK { k }) => Some(*k),
+        _ => None,
+    };
+    let onchange_top_k = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let selection = match input.value().parse() {
+                Ok(k) if k > 0 => Strategy::TopK(TopK { k }),
+                _ => Strategy::default(),
+            };
+            state.dispatch(Action::SetSelection { selection });
+        })
+    };
+    let top_k_settled = top_k.is_some_and(|k| state.humansort_state.top_k_settled(k));
+    // Or they can put every item in an exact order, two at a time.
+    let toggle_exact = {
+        let state = state.clone();
+        let in_session = session.is_some();
+        Callback::from(move |_| {
+            state.dispatch(match in_session {
+                true => Action::EndSession,
+                false => Action::StartSession {
+                    session: Session::Exact(ExactSort),
+                },
+            })
+        })
+    };
+    // Items that haven't been shown yet, such as ones just added to a sorted
+    // list, can be slotted into place with a few questions each.
+    let unplaced: Vec<ItemId> = state
+        .humansort_state
+        .ranked()
+        .filter_map(|item| state.humansort_state.item(item.id))
+        .filter(|item| item.appearances() == 0)
+        .map(|item| item.id())
+        .collect();
+    let can_place = !unplaced.is_empty() && unplaced.len() < state.humansort_state.ranked().len();
+    let place_new = {
+        let state = state.clone();
+        Callback::from(move |_| {
+            let insertion = Insertion::new(&state.humansort_state, &unplaced);
+            state.dispatch(Action::StartSession {
+                session: Session::Insertion(insertion),
+            })
+        })
+    };
+    // Or they can settle the top of the list with a tournament.
+    let tournament = use_state(|| String::from("round-robin"));
+    let onchange_tournament = {
+        let tournament = tournament.clone();
+        Callback::from(move |e: Event| {
+            let target: HtmlSelectElement = e.target_unchecked_into();
+            tournament.set(target.value());
+        })
+    };
+    let entrants = use_state(|| None::<usize>);
+    let onchange_entrants = {
+        let entrants = entrants.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            entrants.set(input.value().parse().ok().filter(|&n| n >= 2));
+        })
+    };
+    let start_tournament = {
+        let state = state.clone();
+        let tournament = tournament.clone();
+        let entrants = entrants.clone();
+        Callback::from(move |_| {
+            let humansort = &state.humansort_state;
+            let ids: Vec<ItemId> = humansort
+                .ranked()
+                .map(|item| item.id)
+                .take(entrants.unwrap_or(usize::MAX))
+                .collect();
+            let session = match tournament.as_str() {
+                "swiss" => Session::Swiss(Swiss::new(humansort, &ids)),
+                "single-elimination" => Session::Bracket(Bracket::new(humansort, &ids, false)),
+                "double-elimination" => Session::Bracket(Bracket::new(humansort, &ids, true)),
+                _ => Session::RoundRobin(RoundRobin::new(humansort, &ids)),
+            };
+            state.dispatch(Action::StartSession { session })
+        })
+    };
+    let standings = match &session {
+        Some(session) if shown_items.is_empty() => session
+            .tournament(&state.humansort_state)
+            .map(|t| t.standings),
+        _ => None,
+    };
+    let exact_order = match &session {
+        Some(Session::Exact(exact)) => match exact.step(&state.humansort_state) {
+            ExactStep::Done(order) => Some(order),
+            ExactStep::Compare(_) => None,
+        },
+        _ => None,
+    };
+    let placed = matches!(session, Some(Session::Insertion(_))) && shown_items.is_empty();
+    let questions_left = session
+        .as_ref()
+        .map(|s| s.questions_left(&state.humansort_state));
+    // If there aren't enough items to pick from, say so instead of showing an
+    // empty prompt.
+    let prompt_error = match session.is_none() && items_to_sort.is_empty() {
+        true => state
+            .humansort_state
+            .next()
+            .err()
+            .map(|e| friendly_message(&e)),
+        false => None,
+    };
     html! {
         <div>
             <button onclick={change_view_input}>{ "🠔 Edit items" }</button>
-            <button onclick={change_view_output}>{ "View sorted list 🠖" }</button>
+            <button onclick={change_view_output.clone()}>{ "View sorted list 🠖" }</button>
+            <div>
+                <button onclick={toggle_exact.clone()}>
+                    {
+                        match session {
+                            Some(Session::Exact(_)) => "Stop sorting exactly",
+                            Some(Session::Insertion(_)) => "Stop placing new items",
+                            Some(_) => "Stop the tournament",
+                            None => "Sort exactly",
+                        }
+                    }
+                </button>
+                if session.is_none() && can_place {
+                    <button onclick={place_new}>{ "Place new items" }</button>
+                }
+                if session.is_none() {
+                    <select onchange={onchange_tournament}>
+                        <option value="round-robin" selected={*tournament == "round-robin"}>
+                            { "Round robin" }
+                        </option>
+                        <option value="swiss" selected={*tournament == "swiss"}>
+                            { "Swiss" }
+                        </option>
+                        <option
+                            value="single-elimination"
+                            selected={*tournament == "single-elimination"}
+                        >
+                            { "Single elimination" }
+                        </option>
+                        <option
+                            value="double-elimination"
+                            selected={*tournament == "double-elimination"}
+                        >
+                            { "Double elimination" }
+                        </option>
+                    </select>
+                    { " between the top " }
+                    <input
+                        type="number"
+                        class={"target"}
+                        min="2"
+                        value={entrants.map(|n| n.to_string()).unwrap_or_default()}
+                        placeholder={"all"}
+                        onchange={onchange_entrants}
+                    />
+                    <button
+                        onclick={start_tournament}
+                        disabled={state.humansort_state.ranked().len() < 2}
+                    >
+                        { "Start tournament" }
+                    </button>
+                }
+                {
+                    match questions_left {
+                        Some(n) if n > 0 => format!(" At most {} more questions.", n),
+                        _ => String::new(),
+                    }
+                }
+            </div>
+            <div>
+                { format!("Roughly {}% settled", settled) }
+                <input
+                    type="number"
+                    class={"target"}
+                    min="1"
+                    max="100"
+                    placeholder={"Stop at %"}
+                    onchange={onchange_target}
+                />
+            </div>
+            <div>
+                { "Focus on the top " }
+                <input
+                    type="number"
+                    class={"target"}
+                    min="1"
+                    value={top_k.map(|k| k.to_string()).unwrap_or_default()}
+                    placeholder={"all"}
+                    onchange={onchange_top_k}
+                />
+                {
+                    match top_k {
+                        Some(k) if top_k_settled => format!("The top {} items are settled.", k),
+                        Some(k) => format!("The top {} items aren't settled yet.", k),
+                        None => String::new(),
+                    }
+                }
+            </div>
+            <div>
+                <button onclick={undo} disabled={!state.humansort_state.can_undo()}>
+                    { "↶ Undo" }
+                </button>
+                <button onclick={redo} disabled={!state.humansort_state.can_redo()}>
+                    { "Redo ↷" }
+                </button>
+            </div>
+            <div>
+                <select onchange={onchange_mode}>
+                    <option value="best" selected={*mode == SortingMode::Best}>
+                        { "Pick the best" }
+                    </option>
+                    <option value="worst" selected={*mode == SortingMode::Worst}>
+                        { "Pick the worst" }
+                    </option>
+                    <option value="ranking" selected={*mode == SortingMode::Ranking}>
+                        { "Rank from best to worst" }
+                    </option>
+                </select>
+            </div>
+            if let Some(order) = exact_order {
+                <div class={"viewContent"}>
+                    <div>{ "The exact order is settled:" }</div>
+                    <ol>
+                        { for order.iter().filter_map(|&id| state.humansort_state.item(id)).map(|item|
+                            html! { <li>{ item.to_string() }</li> }
+                        ) }
+                    </ol>
+                    <button onclick={toggle_exact}>{ "Done" }</button>
+                </div>
+            } else if let Some(standings) = standings {
+                <div class={"viewContent"}>
+                    <div>{ "The tournament is over:" }</div>
+                    <ol>
+                        { for standings.iter().filter_map(|standing| {
+                            let item = state.humansort_state.item(standing.id)?;
+                            Some(html! {
+                                <li>{ format!("{} ({} points)", item, standing.points) }</li>
+                            })
+                        }) }
+                    </ol>
+                    <button onclick={toggle_exact}>{ "Done" }</button>
+                </div>
+            } else if placed {
+                <div class={"viewContent"}>
+                    <div>{ "The new items are in place." }</div>
+                    <button onclick={toggle_exact}>{ "Done" }</button>
+                </div>
+            } else if session.is_none() && (target_reached || top_k_settled) {
+                <div class={"viewContent"}>
+                    if target_reached {
+                        <div>{ format!("That meets your target of {}%.", target.unwrap()) }</div>
+                    }
+                    <button onclick={change_view_output}>{ "View sorted list 🠖" }</button>
+                </div>
+            } else {
             <div class={"viewContent"}>
-                { for items_to_sort.iter().enumerate().map(|(idx, item)| {
-                    let mut others = (*items_to_sort).clone();
-                    others.remove(idx);
+                if let Some(error) = prompt_error {
+                    <div>{ error }</div>
+                }
+                if let Some(question) = state.humansort_state.question() {
+                    <div class={"question"}>{ question }</div>
+                }
+                { for shown_items.iter().map(|&id| {
+                    let value = state
+                        .humansort_state
+                        .item(id)
+                        .map(|item| item.to_string())
+                        .unwrap_or_default();
                     html! {
                         <SortingItem
-                            winner={item.to_string()}
-                            {others}
-                            items_to_sort_setter={items_to_sort.setter()}
-                            state={state.clone()}
+                            {id}
+                            {value}
+                            position={ranked.iter().position(|&r| r == id)}
+                            onselect={onselect.clone()}
                         />
                     }
                 }
                 ) }
+                <div>
+                    <button onclick={tie}>{ "They're equal" }</button>
+                    <button onclick={abstain}>{ "Can't compare" }</button>
+                </div>
+                {
+                    if *mode == SortingMode::Ranking {
+                        html! {
+                            <div>
+                                <button onclick={clear_ranking} disabled={ranked.is_empty()}>
+                                    { "Clear" }
+                                </button>
+                                <button onclick={submit_ranking} disabled={ranked.is_empty()}>
+                                    { "Submit ranking" }
+                                </button>
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
             </div>
+            }
         </div>
     }
 }
@@ -322,7 +915,7 @@ fn OutputView(props: &ViewProps) -> Html {
         })
     };
     let num_items_to_show = use_state(|| {
-        let num_items = state.humansort_state.get_all_items().len();
+        let num_items = state.humansort_state.ranked().len();
         DEFAULT_NUM_ITEMS.min(num_items)
     });
     let showing_all = use_state(|| false);
@@ -331,7 +924,7 @@ fn OutputView(props: &ViewProps) -> Html {
         let num_items_to_show = num_items_to_show.clone();
         let showing_all = showing_all.clone();
         Callback::from(move |_| {
-            let num_items = state.humansort_state.get_all_items().len();
+            let num_items = state.humansort_state.ranked().len();
             showing_all.set(true);
             num_items_to_show.set(num_items);
         })
@@ -341,7 +934,7 @@ fn OutputView(props: &ViewProps) -> Html {
         let num_items_to_show = num_items_to_show.clone();
         let showing_all = showing_all.clone();
         Callback::from(move |_| {
-            let num_items = state.humansort_state.get_all_items().len();
+            let num_items = state.humansort_state.ranked().len();
             showing_all.set(false);
             num_items_to_show.set(DEFAULT_NUM_ITEMS.min(num_items));
         })
@@ -353,13 +946,27 @@ fn OutputView(props: &ViewProps) -> Html {
             <div class={"viewContent"}>
                 <div>
                     { for state.humansort_state
-                        .get_all_items()[..*num_items_to_show].iter().map(|item|
+                        .ranked().take(*num_items_to_show).map(|item|
                         html! {
-                            <div>{ item.to_string() }</div>
+                            <div>
+                                { item.value }
+                                {
+                                    // Show how far each rating might be off so
+                                    // the user knows which ranks to trust.
+                                    match item.uncertainty {
+                                        Some(u) => html! {
+                                            <span class={"uncertainty"}>
+                                                { format!(" ± {:.0}", u) }
+                                            </span>
+                                        },
+                                        None => html! {},
+                                    }
+                                }
+                            </div>
                         }
                     ) }
                 </div>
-                    { if state.humansort_state.get_all_items().len() > DEFAULT_NUM_ITEMS {
+                    { if state.humansort_state.ranked().len() > DEFAULT_NUM_ITEMS {
                         if *showing_all {
                             html! {
                                 <div>
@@ -383,10 +990,20 @@ fn OutputView(props: &ViewProps) -> Html {
 
 #[function_component]
 fn App() -> Html {
-    let state = use_reducer(|| AppState::load_or_default());
+    let state = use_reducer(AppState::load_or_default);
+    let dismiss_error = {
+        let state = state.clone();
+        Callback::from(move |_| state.dispatch(Action::DismissError))
+    };
 
     html! {
         <div class="container">
+            if let Some(error) = &state.error {
+                <div class={"error"}>
+                    { error }
+                    <button onclick={dismiss_error}>{ "OK" }</button>
+                </div>
+            }
             {
                 match state.current_view {
                     AppView::Input => html! { <InputView {state} /> },